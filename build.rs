@@ -46,7 +46,7 @@ fn main() -> anyhow::Result<()> {
 
     writeln!(
         code,
-        "pub static DEFAULT_HIGHLIGHTS: &'static [(&'static str, Highlight<'static>)] = &["
+        "pub static DEFAULT_HIGHLIGHTS: &[(&str, Highlight<'static>)] = &["
     )?;
     for path in fs::read_dir("./default_highlights")? {
         let path = path?.path();
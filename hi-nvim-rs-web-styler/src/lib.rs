@@ -108,7 +108,7 @@ pub fn style_groups<'a>(
 
 /// Parse the color scheme configuration and return a CSS style setting highlight values to
 /// variables for all highlight groups.
-pub fn style(config: &str, target: Target) -> Result<Compiled, hi_nvim_rs::Error> {
+pub fn style(config: &str, target: Target) -> Result<Compiled<'_>, hi_nvim_rs::Error> {
     let colorscheme = hi_nvim_rs::parse(config)?;
     let compiled_colorscheme = match target {
         Target::Neovim => hi_nvim_rs::compile_neovim(&colorscheme)?,
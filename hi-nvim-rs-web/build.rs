@@ -29,7 +29,7 @@ static HIGHLIGHT_NAMES: &[(&str, &str)] = &[
 // Groups specified in HIGHLIGHT_NAMES will be appended.
 static KNOWN_NEOVIM_HIGHLIGHT_GROUPS: &[&str] = &["LineNr", "MsgArea", "StatusLine"];
 
-static COLOR_SCHEMES: &[(&'static str, &'static str)] = &[
+static COLOR_SCHEMES: &[(&str, &str)] = &[
     ("highlow", include_str!("../colorschemes/highlow.toml")),
     ("verf", include_str!("../colorschemes/verf.toml")),
     ("twocolor", include_str!("../colorschemes/twocolor.toml")),
@@ -356,7 +356,7 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let mut buf = BufWriter::new(&mut highlighted_code_file);
                 highlight_html_into_write(
                     &mut highlighter,
-                    &highlight_config,
+                    highlight_config,
                     code.as_bytes(),
                     &mut buf,
                 )?;
@@ -9,7 +9,7 @@ pub struct PrebuiltColorscheme {
     pub neovim_config: &'static str,
 }
 
-pub static HIGHLOW: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
+pub static HIGHLOW: &PrebuiltColorscheme = &PrebuiltColorscheme {
     name: "highlow",
     css_variables: include_str!(concat!(
         env!("OUT_DIR"),
@@ -22,7 +22,7 @@ pub static HIGHLOW: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
     )),
 };
 
-pub static VERF: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
+pub static VERF: &PrebuiltColorscheme = &PrebuiltColorscheme {
     name: "verf",
     css_variables: include_str!(concat!(
         env!("OUT_DIR"),
@@ -35,7 +35,7 @@ pub static VERF: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
     )),
 };
 
-pub static TWOCOLOR: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
+pub static TWOCOLOR: &PrebuiltColorscheme = &PrebuiltColorscheme {
     name: "twocolor",
     css_variables: include_str!(concat!(
         env!("OUT_DIR"),
@@ -48,7 +48,7 @@ pub static TWOCOLOR: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
     )),
 };
 
-pub static GRAYSCALE: &'static PrebuiltColorscheme = &PrebuiltColorscheme {
+pub static GRAYSCALE: &PrebuiltColorscheme = &PrebuiltColorscheme {
     name: "grayscale",
     css_variables: include_str!(concat!(
         env!("OUT_DIR"),
@@ -11,7 +11,7 @@ use axum::{
     routing::{get, post},
     Form, Router,
 };
-use clap::{arg, command, Parser};
+use clap::Parser;
 use serde::Deserialize;
 use tower_http::{compression::CompressionLayer, timeout::TimeoutLayer};
 
@@ -22,6 +22,7 @@ mod error;
 mod examples;
 
 use colorschemes::Colorscheme;
+use error::AppError;
 use examples::Language;
 
 pub static ASSET_LAST_MODIFIED: OnceLock<headers::LastModified> = OnceLock::new();
@@ -32,7 +33,7 @@ pub struct PreviewData<'a> {
     language: Language,
 }
 
-static DESCRIPTION: &'static str = "With this tool you can create Neovim color schemes by defining colors and their transformations in a perceptual color space: Oklab.";
+static DESCRIPTION: &str = "With this tool you can create Neovim color schemes by defining colors and their transformations in a perceptual color space: Oklab.";
 
 const HEADER_VARIES_HTMX: (HeaderName, &str) = (header::VARY, "hx-request");
 
@@ -350,7 +351,7 @@ async fn colorscheme(Form(payload): Form<ColorschemePayload>) -> impl IntoRespon
                 @PreviewContainer { data }
                 section #error ["hx-swap-oob"="true"] {}
                 @Install {
-                    compilation_target: compilation_target,
+                    compilation_target,
                     name,
                     compiled_colorscheme: &compiled_colorscheme,
                 }
@@ -370,14 +371,15 @@ async fn colorscheme(Form(payload): Form<ColorschemePayload>) -> impl IntoRespon
     }
 }
 
-async fn index_post(Form(payload): Form<ColorschemePayload>) -> impl IntoResponse {
+async fn index_post(
+    Form(payload): Form<ColorschemePayload>,
+) -> Result<impl IntoResponse, AppError> {
     let hi_nvim_rs_web_styler::Compiled {
         name,
         css_variables,
         compiled_colorscheme,
         ..
-    } = hi_nvim_rs_web_styler::style(&payload.configuration, payload.compilation_target.into())
-        .unwrap();
+    } = hi_nvim_rs_web_styler::style(&payload.configuration, payload.compilation_target.into())?;
 
     let data = PreviewData {
         theme: &css_variables,
@@ -393,7 +395,7 @@ async fn index_post(Form(payload): Form<ColorschemePayload>) -> impl IntoRespons
         data,
     };
 
-    Html(template.to_string())
+    Ok(Html(template.to_string()))
 }
 
 #[derive(Deserialize)]
@@ -405,7 +407,7 @@ async fn default_colorscheme(Form(payload): Form<DefaultColorschemePayload>) ->
     let colorscheme = payload.colorscheme.colorscheme();
 
     let data = PreviewData {
-        theme: &colorscheme.css_variables,
+        theme: colorscheme.css_variables,
         language: Language::default(),
     };
 
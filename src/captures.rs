@@ -0,0 +1,265 @@
+//! Resolution of tree-sitter captures and Vim syntax groups to highlight groups.
+//!
+//! Color schemes rarely define every capture (e.g., `@keyword.conditional`). Neovim falls back to
+//! the parent capture (`@keyword`), and links many captures and syntax groups to more general
+//! groups by default (`@keyword.conditional` to `Conditional`, which links to `Statement`). Other
+//! editors have no such fallbacks, so when mapping their scopes onto highlight groups, the
+//! fallbacks are resolved here.
+
+use std::collections::BTreeMap;
+
+use crate::colorscheme::Colorscheme;
+use crate::configuration::Highlight;
+
+/// Neovim's default links of tree-sitter captures and Vim syntax groups.
+pub(crate) const DEFAULT_LINKS: &[(&str, &str)] = &[
+    ("@attribute", "Macro"),
+    ("@boolean", "Boolean"),
+    ("@character", "Character"),
+    ("@character.special", "SpecialChar"),
+    ("@comment", "Comment"),
+    ("@constant", "Constant"),
+    ("@constant.builtin", "Special"),
+    ("@constant.macro", "Define"),
+    ("@constructor", "Special"),
+    ("@diff.delta", "Changed"),
+    ("@diff.minus", "Removed"),
+    ("@diff.plus", "Added"),
+    ("@function", "Function"),
+    ("@function.builtin", "Special"),
+    ("@function.macro", "Macro"),
+    ("@keyword", "Keyword"),
+    ("@keyword.conditional", "Conditional"),
+    ("@keyword.debug", "Debug"),
+    ("@keyword.directive", "PreProc"),
+    ("@keyword.directive.define", "Define"),
+    ("@keyword.exception", "Exception"),
+    ("@keyword.import", "PreProc"),
+    ("@keyword.repeat", "Repeat"),
+    ("@keyword.type", "Structure"),
+    ("@label", "Label"),
+    ("@lsp.mod.deprecated", "DiagnosticDeprecated"),
+    ("@lsp.type.class", "@type"),
+    ("@lsp.type.comment", "@comment"),
+    ("@lsp.type.decorator", "@attribute"),
+    ("@lsp.type.enum", "@type"),
+    ("@lsp.type.enumMember", "@constant"),
+    ("@lsp.type.event", "@type"),
+    ("@lsp.type.function", "@function"),
+    ("@lsp.type.interface", "@type"),
+    ("@lsp.type.keyword", "@keyword"),
+    ("@lsp.type.macro", "@constant.macro"),
+    ("@lsp.type.method", "@function.method"),
+    ("@lsp.type.modifier", "@type.qualifier"),
+    ("@lsp.type.namespace", "@module"),
+    ("@lsp.type.number", "@number"),
+    ("@lsp.type.operator", "@operator"),
+    ("@lsp.type.parameter", "@variable.parameter"),
+    ("@lsp.type.property", "@property"),
+    ("@lsp.type.regexp", "@string.regexp"),
+    ("@lsp.type.string", "@string"),
+    ("@lsp.type.struct", "@type"),
+    ("@lsp.type.type", "@type"),
+    ("@lsp.type.typeParameter", "@type.definition"),
+    ("@lsp.type.variable", "@variable"),
+    ("@markup.heading", "Title"),
+    ("@markup.link", "Underlined"),
+    ("@module", "Identifier"),
+    ("@number", "Number"),
+    ("@number.float", "Float"),
+    ("@operator", "Operator"),
+    ("@property", "Identifier"),
+    ("@punctuation", "Delimiter"),
+    ("@punctuation.special", "Special"),
+    ("@string", "String"),
+    ("@string.escape", "SpecialChar"),
+    ("@string.special", "SpecialChar"),
+    ("@tag", "Tag"),
+    ("@type", "Type"),
+    ("@type.builtin", "Special"),
+    ("@type.definition", "Typedef"),
+    ("@variable", "Identifier"),
+    ("Boolean", "Constant"),
+    ("Character", "Constant"),
+    ("Conditional", "Statement"),
+    ("Debug", "Special"),
+    ("Define", "PreProc"),
+    ("Exception", "Statement"),
+    ("Float", "Number"),
+    ("Include", "PreProc"),
+    ("Keyword", "Statement"),
+    ("Label", "Statement"),
+    ("Macro", "PreProc"),
+    ("Number", "Constant"),
+    ("Operator", "Statement"),
+    ("PreCondit", "PreProc"),
+    ("Repeat", "Statement"),
+    ("SpecialChar", "Special"),
+    ("SpecialComment", "Special"),
+    ("StorageClass", "Type"),
+    ("String", "Constant"),
+    ("Structure", "Type"),
+    ("Tag", "Special"),
+    ("Typedef", "Type"),
+];
+
+/// The default mapping of TextMate scopes onto highlight groups and tree-sitter captures. This can
+/// be overridden in the configuration's `[scopes.textmate]` section.
+pub(crate) const TEXTMATE_SCOPES: &[(&str, &str)] = &[
+    ("comment", "@comment"),
+    ("constant", "@constant"),
+    ("constant.character", "@character"),
+    ("constant.character.escape", "@string.escape"),
+    ("constant.language", "@constant.builtin"),
+    ("constant.language.boolean", "@boolean"),
+    ("constant.numeric", "@number"),
+    ("entity.name.class", "@type"),
+    ("entity.name.function", "@function"),
+    ("entity.name.function.macro", "@function.macro"),
+    ("entity.name.namespace", "@module"),
+    ("entity.name.tag", "@tag"),
+    ("entity.name.type", "@type"),
+    ("entity.other.attribute-name", "@tag.attribute"),
+    ("invalid", "Error"),
+    ("keyword", "@keyword"),
+    ("keyword.control", "@keyword.conditional"),
+    ("keyword.control.import", "@keyword.import"),
+    ("keyword.operator", "@operator"),
+    ("markup.bold", "@markup.strong"),
+    ("markup.changed", "diffChanged"),
+    ("markup.deleted", "diffRemoved"),
+    ("markup.heading", "@markup.heading"),
+    ("markup.inline.raw", "@markup.raw"),
+    ("markup.inserted", "diffAdded"),
+    ("markup.italic", "@markup.italic"),
+    ("markup.list", "@markup.list"),
+    ("markup.quote", "@markup.quote"),
+    ("markup.strikethrough", "@markup.strikethrough"),
+    ("markup.underline.link", "@markup.link.url"),
+    ("meta.preprocessor", "@keyword.directive"),
+    ("punctuation", "@punctuation"),
+    ("punctuation.definition.tag", "@tag.delimiter"),
+    ("punctuation.section.embedded", "@punctuation.special"),
+    ("storage", "@keyword"),
+    ("storage.modifier", "@keyword.modifier"),
+    ("string", "@string"),
+    ("string.regexp", "@string.regexp"),
+    ("support.class", "@type"),
+    ("support.function", "@function.builtin"),
+    ("support.type", "@type.builtin"),
+    ("variable", "@variable"),
+    ("variable.language", "@variable.builtin"),
+    ("variable.other.constant", "@constant"),
+    ("variable.other.member", "@variable.member"),
+    ("variable.other.property", "@property"),
+    ("variable.parameter", "@variable.parameter"),
+];
+
+/// The default mapping of LSP semantic token types onto highlight groups and tree-sitter
+/// captures. This can be overridden in the configuration's `[scopes.semantic-tokens]` section.
+pub(crate) const SEMANTIC_TOKEN_SCOPES: &[(&str, &str)] = &[
+    ("class", "@lsp.type.class"),
+    ("comment", "@lsp.type.comment"),
+    ("decorator", "@lsp.type.decorator"),
+    ("enum", "@lsp.type.enum"),
+    ("enumMember", "@lsp.type.enumMember"),
+    ("event", "@lsp.type.event"),
+    ("function", "@lsp.type.function"),
+    ("interface", "@lsp.type.interface"),
+    ("keyword", "@lsp.type.keyword"),
+    ("macro", "@lsp.type.macro"),
+    ("method", "@lsp.type.method"),
+    ("namespace", "@lsp.type.namespace"),
+    ("number", "@lsp.type.number"),
+    ("operator", "@lsp.type.operator"),
+    ("parameter", "@lsp.type.parameter"),
+    ("property", "@lsp.type.property"),
+    ("regexp", "@lsp.type.regexp"),
+    ("string", "@lsp.type.string"),
+    ("struct", "@lsp.type.struct"),
+    ("type", "@lsp.type.type"),
+    ("typeParameter", "@lsp.type.typeParameter"),
+    ("variable", "@lsp.type.variable"),
+];
+
+/// Get the highlight of a highlight group, tree-sitter capture or Vim syntax group, falling back
+/// as Neovim does if the color scheme does not define it. Links are followed. Returns `None` if no
+/// fallback is defined either.
+pub fn resolve<'a>(colorscheme: &Colorscheme<'a>, group: &str) -> Option<Highlight<'a>> {
+    let mut group = group;
+    loop {
+        if let Some(highlight) = colorscheme.resolved_highlight(group) {
+            return Some(highlight);
+        }
+
+        group = if let Some(&(_, link)) = DEFAULT_LINKS.iter().find(|(from, _)| *from == group) {
+            link
+        } else if let Some((parent, _)) = group.strip_prefix('@').and(group.rsplit_once('.')) {
+            parent
+        } else {
+            return None;
+        };
+    }
+}
+
+/// The targets whose scopes can be mapped onto highlight groups in the configuration's
+/// `[scopes.<target>]` section.
+const SCOPE_TARGETS: [&str; 13] = [
+    "helix",
+    "textmate",
+    "semantic-tokens",
+    "zed",
+    "emacs",
+    "kakoune",
+    "micro",
+    "jetbrains",
+    "pygments",
+    "fzf",
+    "delta",
+    "lazygit",
+    "tmux",
+];
+
+/// Whether `name` names a target with a `[scopes.<target>]` section.
+pub(crate) fn is_scope_target(name: &str) -> bool {
+    SCOPE_TARGETS.contains(&name)
+}
+
+/// The mapping of a target's scopes onto highlight groups: the `defaults`, overridden by the
+/// configuration's `[scopes.<target>]` section. Scopes mapped to an empty string in the
+/// configuration are removed.
+pub(crate) fn scope_mapping<'a>(
+    colorscheme: &Colorscheme<'a>,
+    target: &str,
+    defaults: &[(&'a str, &'a str)],
+) -> BTreeMap<&'a str, &'a str> {
+    let mut mapping: BTreeMap<_, _> = defaults.iter().copied().collect();
+    if let Some(overrides) = colorscheme.scopes.get(target) {
+        for (&scope, &group) in overrides {
+            if group.is_empty() {
+                mapping.remove(scope);
+            } else {
+                mapping.insert(scope, group);
+            }
+        }
+    }
+    mapping
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve;
+
+    #[test]
+    fn neovim_fallbacks() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let statement = colorscheme.highlight("Statement");
+        let keyword = colorscheme.highlight("Keyword");
+
+        // `@keyword.conditional` -> `Conditional` -> `Statement`
+        assert!(resolve(&colorscheme, "@keyword.conditional") == statement);
+        // `@keyword.coroutine` -> `@keyword` -> `Keyword`
+        assert!(resolve(&colorscheme, "@keyword.coroutine") == keyword);
+        assert!(resolve(&colorscheme, "NoSuchGroup").is_none());
+    }
+}
@@ -1,16 +1,18 @@
 #![allow(unused)]
 
-use color::{Oklch, OpaqueColor, Srgb};
+use color::{Oklab, Oklch, OpaqueColor, Srgb};
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
-use crate::configuration::{Highlight, Kind, NamespacedThemeElement};
+use crate::configuration::{Highlight, Kind, NamespacedThemeElement, ThemeElement};
 use crate::error::Error;
+use crate::terminal16::Palette;
 
 /// Non-linear 8-bit per channel sRGB.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Rgb8 {
     pub red: u8,
     pub green: u8,
@@ -29,6 +31,44 @@ impl From<OpaqueColor<Oklch>> for Rgb8 {
     }
 }
 
+impl Rgb8 {
+    /// Convert to a floating point sRGB color.
+    pub fn to_srgb(self) -> OpaqueColor<Srgb> {
+        OpaqueColor::new([
+            f32::from(self.red) / 255.,
+            f32::from(self.green) / 255.,
+            f32::from(self.blue) / 255.,
+        ])
+    }
+
+    /// The perceptual difference between two colors, measured as the Euclidean distance in the
+    /// Oklab color space (DeltaEOK).
+    pub fn delta_eok(self, other: Rgb8) -> f32 {
+        self.to_srgb()
+            .convert::<Oklab>()
+            .difference(other.to_srgb().convert::<Oklab>())
+    }
+}
+
+/// Parses colors in hexadecimal notation (e.g., `#3d3d3d`). The leading `#` is optional.
+impl FromStr for Rgb8 {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidHexColor(s.to_owned()));
+        }
+
+        let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap();
+        Ok(Rgb8 {
+            red: channel(0),
+            green: channel(2),
+            blue: channel(4),
+        })
+    }
+}
+
 impl Display for Rgb8 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let Rgb8 { red, green, blue } = self;
@@ -37,27 +77,80 @@ impl Display for Rgb8 {
 }
 
 /// A handle to the colors of a theme.
+#[derive(Clone)]
 pub struct Theme<'a> {
     pub(crate) colors: BTreeMap<&'a str, BTreeMap<&'a str, Rgb8>>,
+    /// The colors of the theme elements as computed, before mapping them into the sRGB gamut.
+    pub(crate) oklch: BTreeMap<&'a str, BTreeMap<&'a str, OpaqueColor<Oklch>>>,
 }
 
-impl Theme<'_> {
+impl<'a> Theme<'a> {
     pub fn get_color(&self, theme_element: NamespacedThemeElement) -> Option<Rgb8> {
         self.colors
             .get(theme_element.theme_namespace)?
             .get(theme_element.element_name)
             .copied()
     }
+
+    /// Get the color of a theme element before it was mapped into the sRGB gamut.
+    pub(crate) fn get_oklch(
+        &self,
+        theme_element: NamespacedThemeElement,
+    ) -> Option<OpaqueColor<Oklch>> {
+        self.oklch
+            .get(theme_element.theme_namespace)?
+            .get(theme_element.element_name)
+            .copied()
+    }
+
+    /// Get the color of a theme element by its full name (e.g., `ui.bg`).
+    pub fn get_color_by_name(&self, theme_element: &str) -> Option<Rgb8> {
+        let (theme_namespace, element_name) = theme_element.split_once('.')?;
+        self.get_color(NamespacedThemeElement {
+            theme_namespace,
+            element_name,
+        })
+    }
+
+    /// An iterator over the theme elements and their colors, sorted by namespace and element
+    /// name.
+    pub fn elements(&self) -> impl Iterator<Item = (NamespacedThemeElement<'a>, Rgb8)> + '_ {
+        self.colors.iter().flat_map(|(&theme_namespace, elements)| {
+            elements.iter().map(move |(&element_name, &color)| {
+                (
+                    NamespacedThemeElement {
+                        theme_namespace,
+                        element_name,
+                    },
+                    color,
+                )
+            })
+        })
+    }
 }
 
 /// A Neovim colorscheme. It consists of a light theme and a dark theme. These themes contain
 /// colors. The highlights refer to elements in these themes.
+#[derive(Clone)]
 pub struct Colorscheme<'a> {
     pub(crate) name: &'a str,
     pub(crate) kind: Kind,
     pub(crate) light_theme: Theme<'a>,
     pub(crate) dark_theme: Theme<'a>,
     pub(crate) highlights: BTreeMap<&'a str, Highlight<'a>>,
+    pub(crate) terminal16: Option<Palette>,
+    /// Terminal colors declared as references to theme elements, keyed by terminal color name.
+    pub(crate) terminal: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    /// base16 palette slots declared as references to theme elements, keyed by slot name.
+    pub(crate) base16: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    /// Desktop theme slots declared as references to theme elements, keyed by slot name.
+    pub(crate) desktop: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    /// Per target, overrides of the mapping of the target's scopes onto highlight groups.
+    pub(crate) scopes: BTreeMap<&'a str, BTreeMap<&'a str, &'a str>>,
+    /// The definitions of the theme elements, keyed by theme namespace and element name.
+    pub(crate) definitions: BTreeMap<(&'a str, &'a str), ThemeElement<'a>>,
+    /// A highlight group that is part of a link cycle, if any.
+    pub(crate) link_cycle: Option<String>,
 }
 
 impl<'a> Colorscheme<'a> {
@@ -73,15 +166,23 @@ impl<'a> Colorscheme<'a> {
     }
 
     /// The colors of the light theme.
-    pub fn light_theme(&self) -> &Theme {
+    pub fn light_theme(&self) -> &Theme<'a> {
         &self.light_theme
     }
 
     /// The colors of the dark theme.
-    pub fn dark_theme(&self) -> &Theme {
+    pub fn dark_theme(&self) -> &Theme<'a> {
         &self.dark_theme
     }
 
+    /// The colors of the light or dark theme.
+    pub fn theme(&self, background: Kind) -> &Theme<'a> {
+        match background {
+            Kind::Light => &self.light_theme,
+            Kind::Dark => &self.dark_theme,
+        }
+    }
+
     pub fn get_color(&self, theme_element: NamespacedThemeElement<'_>) -> Option<Rgb8> {
         match self.kind {
             Kind::Light => self.light_theme.get_color(theme_element),
@@ -97,7 +198,7 @@ impl<'a> Colorscheme<'a> {
     }
 
     /// An iterator over the highlight groups.
-    pub fn highlights(&self) -> impl Iterator<Item = (&str, Highlight)> {
+    pub fn highlights(&self) -> impl Iterator<Item = (&'a str, Highlight<'a>)> + '_ {
         self.highlights
             .iter()
             .map(|(&group, highlight)| (group, *highlight))
@@ -107,6 +208,49 @@ impl<'a> Colorscheme<'a> {
     pub fn highlight(&self, group: &str) -> Option<Highlight<'a>> {
         self.highlights.get(group).copied()
     }
+
+    /// Get a highlight group by name. If the group links to another group, the group it links to
+    /// is returned instead. Returns `None` if either group does not exist, or if the link is part
+    /// of a cycle.
+    pub fn resolved_highlight(&self, group: &str) -> Option<Highlight<'a>> {
+        let mut highlight = self.highlight(group)?;
+        // Links are reduced to a depth of 1 when parsing, unless the configuration contains a link
+        // cycle. Then links are followed until they would have visited every group.
+        for _ in 0..self.highlights.len() {
+            match highlight.link {
+                Some(link) => highlight = self.highlight(link)?,
+                None => return Some(highlight),
+            }
+        }
+        None
+    }
+
+    /// Whether two theme elements are defined identically, i.e., they refer to the same color with
+    /// the same modifiers. Such elements intentionally share a color.
+    pub fn same_definition(
+        &self,
+        element1: NamespacedThemeElement<'_>,
+        element2: NamespacedThemeElement<'_>,
+    ) -> bool {
+        let definition1 = self
+            .definitions
+            .get(&(element1.theme_namespace, element1.element_name));
+        let definition2 = self
+            .definitions
+            .get(&(element2.theme_namespace, element2.element_name));
+        definition1.is_some() && definition1 == definition2
+    }
+
+    /// A highlight group that is part of a link cycle, if the configuration contains one. Such
+    /// configurations are accepted, leaving the cycle for (Neo)vim to report.
+    pub fn link_cycle(&self) -> Option<&str> {
+        self.link_cycle.as_deref()
+    }
+
+    /// The terminal's 16 ANSI colors, if specified in the `[terminal16]` configuration section.
+    pub fn terminal16(&self) -> Option<&Palette> {
+        self.terminal16.as_ref()
+    }
 }
 
 /// Reduce all links to a depth of 1 (i.e., after this transformation, all groups that are linked
@@ -159,14 +303,21 @@ fn reduce_link_depth<'a>(highlights: &mut BTreeMap<&'a str, Highlight<'a>>) -> R
 /// Parse and transform a `hi.nvim.rs` color scheme TOML configuration into a Neovim color scheme
 /// specification.
 pub fn parse<'a>(colorscheme_config: &'a str) -> Result<Colorscheme<'a>, Error> {
-    let config = {
-        let mut config = crate::configuration::parse(&colorscheme_config)?;
-        reduce_link_depth(&mut config.highlights);
-        config
+    let mut config = crate::configuration::parse(colorscheme_config)?;
+    // Link cycles are left for (Neo)vim to report, keeping the links as written.
+    let link_cycle = match reduce_link_depth(&mut config.highlights) {
+        Err(Error::LinkCycle(group)) => Some(group),
+        result => {
+            result?;
+            None
+        }
     };
 
     let mut theme: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
     let mut inverse_theme: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+    let mut oklch: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+    let mut inverse_oklch: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+    let mut definitions = BTreeMap::new();
 
     // calculate all theme element colors (for both the normal and inverse themes)
     for (&theme_namespace, elements) in config.themes.0.iter() {
@@ -192,26 +343,33 @@ pub fn parse<'a>(colorscheme_config: &'a str) -> Result<Colorscheme<'a>, Error>
                 .entry(theme_namespace)
                 .or_default()
                 .insert(element_name, Rgb8::from(inverse_color));
+            oklch
+                .entry(theme_namespace)
+                .or_default()
+                .insert(element_name, color);
+            inverse_oklch
+                .entry(theme_namespace)
+                .or_default()
+                .insert(element_name, inverse_color);
+            definitions.insert((theme_namespace, element_name), element.0);
         }
     }
 
+    let theme = Theme {
+        colors: theme,
+        oklch,
+    };
+    let inverse_theme = Theme {
+        colors: inverse_theme,
+        oklch: inverse_oklch,
+    };
     let (light_theme, dark_theme) = match config.kind {
-        Kind::Light => (
-            Theme { colors: theme },
-            Theme {
-                colors: inverse_theme,
-            },
-        ),
-        Kind::Dark => (
-            Theme {
-                colors: inverse_theme,
-            },
-            Theme { colors: theme },
-        ),
+        Kind::Light => (theme, inverse_theme),
+        Kind::Dark => (inverse_theme, theme),
     };
 
     // check whether all referenced theme elements exist
-    for (_, highlight) in &config.highlights {
+    for highlight in config.highlights.values() {
         if let Some(fg) = highlight.fg {
             if light_theme.get_color(fg).is_none() {
                 return Err(Error::ThemeElementMissing(format!("{}", fg)));
@@ -224,11 +382,100 @@ pub fn parse<'a>(colorscheme_config: &'a str) -> Result<Colorscheme<'a>, Error>
         }
     }
 
-    Ok(Colorscheme {
+    // check whether the terminal colors are valid
+    for (&name, &element) in &config.terminal {
+        if !crate::terminal::is_terminal_color(name) {
+            return Err(Error::UnknownTerminalColor(name.to_owned()));
+        }
+        if light_theme.get_color(element).is_none() {
+            return Err(Error::ThemeElementMissing(format!("{}", element)));
+        }
+    }
+
+    // check whether the base16 palette slots are valid
+    for (&name, &element) in &config.base16 {
+        if !crate::compiler_base16::is_slot(name) {
+            return Err(Error::UnknownBase16Slot(name.to_owned()));
+        }
+        if light_theme.get_color(element).is_none() {
+            return Err(Error::ThemeElementMissing(format!("{}", element)));
+        }
+    }
+
+    // check whether the desktop theme slots are valid
+    for (&name, &element) in &config.desktop {
+        if !crate::compiler_desktop::is_slot(name) {
+            return Err(Error::UnknownDesktopSlot(name.to_owned()));
+        }
+        if light_theme.get_color(element).is_none() {
+            return Err(Error::ThemeElementMissing(format!("{}", element)));
+        }
+    }
+
+    // check whether the scope mapping targets are valid
+    for &target in config.scopes.keys() {
+        if !crate::captures::is_scope_target(target) {
+            return Err(Error::UnknownScopeTarget(target.to_owned()));
+        }
+    }
+
+    let colorscheme = Colorscheme {
         name: config.name,
         kind: config.kind,
         light_theme,
         dark_theme,
         highlights: config.highlights,
-    })
+        terminal16: config.terminal16,
+        terminal: config.terminal,
+        base16: config.base16,
+        desktop: config.desktop,
+        scopes: config.scopes,
+        definitions,
+        link_cycle,
+    };
+    crate::terminal::ansi_elements(&colorscheme)?;
+
+    // check whether the highlight groups scopes are mapped onto exist
+    for &group in colorscheme.scopes.values().flat_map(BTreeMap::values) {
+        if !group.is_empty() && crate::captures::resolve(&colorscheme, group).is_none() {
+            return Err(Error::HighlightGroupMissing(group.to_owned()));
+        }
+    }
+
+    Ok(colorscheme)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn link_cycles() {
+        let config = include_str!("../colorschemes/highlow.toml").replacen(
+            "[highlights]\n",
+            "[highlights]\n\
+             CycleA = { link = \"CycleB\" }\n\
+             CycleB = { link = \"CycleA\" }\n\
+             Nested = { link = \"Linked\" }\n\
+             Linked = { link = \"StatusLine\" }\n",
+            1,
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+
+        assert!(colorscheme.link_cycle().is_some());
+        assert!(colorscheme.resolved_highlight("CycleA").is_none());
+        // links outside the cycle are followed to their final group
+        assert!(colorscheme.resolved_highlight("Nested") == colorscheme.highlight("StatusLine"));
+    }
+
+    #[test]
+    fn scope_targets() {
+        let config = include_str!("../colorschemes/highlow.toml");
+        let parse = |target| {
+            let config = format!("{config}\n[scopes.{target}]\nkeyword = \"Keyword\"\n");
+            crate::parse(&config).err().map(|error| error.to_string())
+        };
+
+        assert_eq!(parse("helix"), None);
+        assert_eq!(parse("semantic-tokens"), None);
+        assert!(parse("vscode").is_some_and(|error| error.ends_with(": vscode")));
+    }
 }
@@ -0,0 +1,198 @@
+//! base16 and base24 palette compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use color::{Oklch, OpaqueColor};
+
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::compiler_common::finish;
+use crate::configuration::Kind;
+use crate::error::Error;
+
+/// Where a palette slot takes its color from, unless overridden.
+#[derive(Clone, Copy)]
+enum Source {
+    /// A theme element.
+    Element(&'static str),
+    /// The background (`base00`), darkened by this Oklch lightness.
+    Darker(f32),
+}
+
+/// The default mapping of palette slots onto theme elements, in slot order. The first 16 slots
+/// make up a base16 palette, all 24 a base24 palette. This can be overridden in the
+/// configuration's `[base16]` section.
+const SLOTS: [(&str, Source); 24] = [
+    // backgrounds and foregrounds, from the background to the foreground
+    ("base00", Source::Element("ui.bg")),
+    ("base01", Source::Element("ui.bg_gutter")),
+    ("base02", Source::Element("ui.bg_visual")),
+    ("base03", Source::Element("ui.fg_very_dim")),
+    ("base04", Source::Element("ui.fg_dim")),
+    ("base05", Source::Element("ui.fg")),
+    ("base06", Source::Element("ui.float_fg")),
+    ("base07", Source::Element("ui.pmenu_fg")),
+    // accents: red, orange, yellow, green, cyan, blue, purple and brown
+    ("base08", Source::Element("vcs.removed")),
+    ("base09", Source::Element("syn.number")),
+    ("base0A", Source::Element("vcs.changed")),
+    ("base0B", Source::Element("syn.string")),
+    ("base0C", Source::Element("syn.constant")),
+    ("base0D", Source::Element("syn.function")),
+    ("base0E", Source::Element("syn.keyword")),
+    ("base0F", Source::Element("syn.constructor")),
+    // base24: darker backgrounds, and bright red, yellow, green, cyan, blue and purple
+    ("base10", Source::Darker(0.04)),
+    ("base11", Source::Darker(0.08)),
+    ("base12", Source::Element("diagnostics.error")),
+    ("base13", Source::Element("diagnostics.warning")),
+    ("base14", Source::Element("diagnostics.ok")),
+    ("base15", Source::Element("syn.comment")),
+    ("base16", Source::Element("diagnostics.info")),
+    ("base17", Source::Element("syn.type")),
+];
+
+/// The number of slots holding the backgrounds and foregrounds (`base00` to `base07`).
+const RAMP: usize = 8;
+
+/// Whether `name` names a base24 palette slot (`base00` to `base17`).
+pub(crate) fn is_slot(name: &str) -> bool {
+    SLOTS.iter().any(|(slot, _)| *slot == name)
+}
+
+fn lightness(color: Rgb8) -> f32 {
+    color.to_srgb().convert::<Oklch>().components[0]
+}
+
+fn compile_program(colorscheme: &Colorscheme, background: Kind, slots: usize) -> Result<String> {
+    let theme = colorscheme.theme(background);
+    let mut program = String::with_capacity(1_024);
+
+    writeln!(program, "# Generated by hi.nvim.rs.")?;
+    writeln!(program, "system: \"base{slots}\"")?;
+    writeln!(
+        program,
+        "name: {:?}",
+        format!("{}-{background}", colorscheme.name())
+    )?;
+    writeln!(program, "author: \"hi.nvim.rs\"")?;
+    writeln!(program, "variant: \"{background}\"")?;
+    writeln!(program, "palette:")?;
+
+    let mut colors = Vec::with_capacity(slots);
+    for &(slot, default) in &SLOTS[..slots] {
+        let element = match (colorscheme.base16.get(slot), default) {
+            (Some(&element), _) => format!("{element}"),
+            (None, Source::Element(element)) => element.to_owned(),
+            (None, Source::Darker(_)) => {
+                colors.push(None);
+                continue;
+            }
+        };
+        let color = theme
+            .get_color_by_name(&element)
+            .ok_or(Error::ThemeElementMissing(element))?;
+        colors.push(Some(color));
+    }
+
+    // The backgrounds and foregrounds must ramp from the background to the foreground.
+    colors[..RAMP].sort_by(|a, b| lightness(a.unwrap()).total_cmp(&lightness(b.unwrap())));
+    if background == Kind::Light {
+        colors[..RAMP].reverse();
+    }
+
+    let base00 = colors[0].unwrap().to_srgb().convert::<Oklch>();
+    for (&(slot, default), color) in SLOTS.iter().zip(colors) {
+        let color = match (color, default) {
+            (Some(color), _) => color,
+            (None, Source::Darker(amount)) => {
+                let [l, c, h] = base00.components;
+                Rgb8::from(OpaqueColor::<Oklch>::new([(l - amount).max(0.), c, h]))
+            }
+            (None, Source::Element(_)) => unreachable!("elements are looked up above"),
+        };
+        writeln!(program, "  {slot}: \"{color}\"")?;
+    }
+
+    Ok(program)
+}
+
+fn compile(colorscheme: &Colorscheme, background: Kind, slots: usize) -> Result<String, Error> {
+    finish(compile_program(colorscheme, background, slots))
+}
+
+/// Compile the light or dark theme of a color scheme to a base16 palette, in the YAML scheme
+/// format of tinted-theming.
+///
+/// The slots are taken from theme elements: `base00` to `base07` from the `ui` elements, ordered
+/// by lightness from the background to the foreground, and the accents from `syn`, `vcs` and
+/// `diagnostics` elements of the matching colors. The mapping can be overridden in the
+/// configuration's `[base16]` section.
+pub fn compile_base16(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    compile(colorscheme, background, 16)
+}
+
+/// Compile the light or dark theme of a color scheme to a base24 palette. This extends the
+/// base16 palette with `base10` to `base17`: two darker shades of the background, and bright
+/// accents taken from the `diagnostics` and `syn` elements by default.
+pub fn compile_base24(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    compile(colorscheme, background, 24)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile_base24, lightness, RAMP};
+    use crate::colorscheme::Rgb8;
+    use crate::configuration::Kind;
+    use crate::error::Error;
+
+    fn palette(config: &str, background: Kind) -> Result<Vec<Rgb8>, Error> {
+        let colorscheme = crate::parse(config)?;
+        let program = compile_base24(&colorscheme, background)?;
+        Ok(program
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("base"))
+            .map(|line| line[4..].trim_matches('"').parse().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn default_palette() {
+        let config = include_str!("../colorschemes/highlow.toml");
+        for background in [Kind::Light, Kind::Dark] {
+            let colors = palette(config, background).unwrap();
+            assert_eq!(colors.len(), 24);
+
+            // base00 to base07 ramp from the background to the foreground
+            let mut ramp: Vec<_> = colors[..RAMP]
+                .iter()
+                .map(|&color| lightness(color))
+                .collect();
+            if background == Kind::Light {
+                ramp.reverse();
+            }
+            assert!(ramp.windows(2).all(|pair| pair[0] < pair[1]));
+
+            // base10 and base11 are darker than base00
+            assert!(lightness(colors[16]) < lightness(colors[0]));
+            assert!(lightness(colors[17]) < lightness(colors[16]));
+
+            let accents: Vec<_> = colors[8..16].iter().chain(&colors[18..]).collect();
+            for (i, accent) in accents.iter().enumerate() {
+                assert!(!accents[..i].contains(accent));
+            }
+        }
+    }
+
+    #[test]
+    fn missing_override() {
+        let config = format!(
+            "{}\n[base16]\nbase0C = \"syn.missing\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        assert!(matches!(
+            palette(&config, Kind::Dark),
+            Err(Error::ThemeElementMissing(element)) if element == "syn.missing"
+        ));
+    }
+}
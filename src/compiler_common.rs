@@ -0,0 +1,11 @@
+//! Helpers shared by the compilers.
+
+use crate::error::Error;
+
+/// Finish compiling a theme, reporting errors (e.g., failing to write the program) as
+/// [`Error::CompilationFailed`]. Trailing newlines are trimmed, as these are added when writing
+/// the theme.
+pub(crate) fn finish(program: anyhow::Result<String>) -> Result<String, Error> {
+    let program = program.map_err(|_| Error::CompilationFailed)?;
+    Ok(program.trim_end().to_owned())
+}
@@ -0,0 +1,326 @@
+//! CSS compilation, for highlighting code on web pages.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use color::{ColorSpace, DisplayP3, Oklch, OpaqueColor};
+
+use crate::captures;
+use crate::colorscheme::Colorscheme;
+use crate::compiler_common::finish;
+use crate::compiler_json::components;
+use crate::configuration::{Highlight, Kind, NamespacedThemeElement};
+use crate::error::Error;
+
+/// The gamut of the colors of a CSS style sheet. The sRGB colors in hexadecimal notation are
+/// always written; wider gamuts are written as `oklch()` colors for browsers supporting them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Gamut {
+    /// Only sRGB colors.
+    Srgb,
+    /// Colors mapped into the Display-P3 gamut, by reducing their chroma.
+    DisplayP3,
+    /// Colors as computed from the configuration, leaving gamut mapping to the browser.
+    #[default]
+    Unbounded,
+}
+
+/// Escape the characters of an identifier that are not allowed in CSS identifiers unescaped
+/// (e.g., the `@` and `.` of tree-sitter captures).
+fn escape(identifier: &str) -> String {
+    let mut escaped = String::with_capacity(identifier.len());
+    for c in identifier.chars() {
+        if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The custom property holding the color of a theme element (e.g., `--hl-ui-bg` for `ui.bg`).
+fn property(element: NamespacedThemeElement) -> String {
+    format!(
+        "--hl-{}-{}",
+        escape(element.theme_namespace),
+        escape(element.element_name)
+    )
+}
+
+/// Format a number with at most `precision` decimals, without trailing zeros.
+fn number(value: f32, precision: usize) -> String {
+    let formatted = format!("{value:.precision$}");
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+    match formatted {
+        "-0" => "0".to_owned(),
+        _ => formatted.to_owned(),
+    }
+}
+
+fn compile_properties(
+    program: &mut String,
+    colorscheme: &Colorscheme,
+    background: Kind,
+    gamut: Gamut,
+    indent: &str,
+) -> Result<()> {
+    let theme = colorscheme.theme(background);
+    for (element, color) in theme.elements() {
+        let oklch = match gamut {
+            Gamut::Srgb => {
+                writeln!(program, "{indent}  {}: {color};", property(element))?;
+                continue;
+            }
+            Gamut::DisplayP3 => theme.get_oklch(element).map(|oklch| {
+                let p3 = crate::gamut_map::reduce_chroma::<DisplayP3>(
+                    oklch.convert::<DisplayP3>().components,
+                    0.02,
+                );
+                OpaqueColor::new(DisplayP3::convert::<Oklch>(p3))
+            }),
+            Gamut::Unbounded => theme.get_oklch(element),
+        };
+        let [l, c, h] =
+            components(oklch.ok_or_else(|| Error::ThemeElementMissing(format!("{element}")))?);
+        writeln!(
+            program,
+            "{indent}  {}: oklch({} {} {});",
+            property(element),
+            number(l, 4),
+            number(c, 4),
+            number(h, 2),
+        )?;
+    }
+    Ok(())
+}
+
+/// Write the custom properties of the theme elements for one or both backgrounds.
+fn compile_root(
+    program: &mut String,
+    colorscheme: &Colorscheme,
+    background: Option<Kind>,
+    gamut: Gamut,
+    indent: &str,
+) -> Result<()> {
+    match background {
+        Some(background) => {
+            writeln!(program, "{indent}:root {{")?;
+            if gamut == Gamut::Srgb {
+                writeln!(program, "{indent}  color-scheme: {background};")?;
+            }
+            compile_properties(program, colorscheme, background, gamut, indent)?;
+            writeln!(program, "{indent}}}")?;
+        }
+        None => {
+            let (normal, inverse) = match colorscheme.kind() {
+                Kind::Light => (Kind::Light, Kind::Dark),
+                Kind::Dark => (Kind::Dark, Kind::Light),
+            };
+            writeln!(program, "{indent}:root {{")?;
+            if gamut == Gamut::Srgb {
+                writeln!(program, "{indent}  color-scheme: {normal} {inverse};")?;
+            }
+            compile_properties(program, colorscheme, normal, gamut, indent)?;
+            writeln!(program, "{indent}}}")?;
+            writeln!(program)?;
+            writeln!(
+                program,
+                "{indent}@media (prefers-color-scheme: {inverse}) {{"
+            )?;
+            writeln!(program, "{indent}  :root {{")?;
+            compile_properties(program, colorscheme, inverse, gamut, &format!("{indent}  "))?;
+            writeln!(program, "{indent}  }}")?;
+            writeln!(program, "{indent}}}")?;
+        }
+    }
+    Ok(())
+}
+
+fn compile_class(program: &mut String, group: &str, highlight: Highlight) -> Result<()> {
+    let mut declarations = Vec::new();
+
+    let (fg, bg) = match highlight.reverse.unwrap_or(false) {
+        true => (highlight.bg, highlight.fg),
+        false => (highlight.fg, highlight.bg),
+    };
+    if let Some(fg) = fg {
+        declarations.push(format!("color: var({})", property(fg)));
+    }
+    if let Some(bg) = bg {
+        declarations.push(format!("background-color: var({})", property(bg)));
+    }
+    if highlight.bold.unwrap_or(false) {
+        declarations.push("font-weight: bold".to_owned());
+    }
+    if highlight.italic.unwrap_or(false) {
+        declarations.push("font-style: italic".to_owned());
+    }
+
+    let underline = [
+        (highlight.underline, "solid"),
+        (highlight.undercurl, "wavy"),
+        (highlight.underdouble, "double"),
+        (highlight.underdotted, "dotted"),
+        (highlight.underdashed, "dashed"),
+    ]
+    .into_iter()
+    .find_map(|(set, style)| set.unwrap_or(false).then_some(style));
+    let strikethrough = highlight.strikethrough.unwrap_or(false);
+
+    let lines: Vec<_> = [
+        underline.map(|_| "underline"),
+        strikethrough.then_some("line-through"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !lines.is_empty() {
+        declarations.push(format!("text-decoration-line: {}", lines.join(" ")));
+        if let Some(style) = underline.filter(|&style| style != "solid") {
+            declarations.push(format!("text-decoration-style: {style}"));
+        }
+        if let Some(sp) = highlight.sp {
+            declarations.push(format!("text-decoration-color: var({})", property(sp)));
+        }
+    }
+
+    if declarations.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(program)?;
+    writeln!(program, ".hl-{} {{", escape(group))?;
+    for declaration in declarations {
+        writeln!(program, "  {declaration};")?;
+    }
+    writeln!(program, "}}")?;
+
+    Ok(())
+}
+
+fn compile_program(
+    colorscheme: &Colorscheme,
+    background: Option<Kind>,
+    gamut: Gamut,
+) -> Result<String> {
+    let mut program = String::with_capacity(16_384);
+
+    writeln!(program, "/* Generated by hi.nvim.rs. */")?;
+    writeln!(program)?;
+
+    compile_root(&mut program, colorscheme, background, Gamut::Srgb, "")?;
+    if gamut != Gamut::Srgb {
+        // Custom properties are not validated when declared, so a later declaration cannot fall
+        // back to an earlier one. The sRGB colors are instead overridden where `oklch()` is
+        // supported.
+        writeln!(program)?;
+        writeln!(program, "@supports (color: oklch(0 0 0)) {{")?;
+        compile_root(&mut program, colorscheme, background, gamut, "  ")?;
+        writeln!(program, "}}")?;
+    }
+
+    for (group, highlight) in colorscheme.highlights() {
+        // links are followed to their final group, falling back as Neovim does
+        let highlight = match highlight.link {
+            Some(link) => match captures::resolve(colorscheme, link) {
+                Some(highlight) => highlight,
+                None => continue,
+            },
+            None => highlight,
+        };
+        compile_class(&mut program, group, highlight)?;
+    }
+
+    Ok(program)
+}
+
+/// Compile a color scheme to a CSS style sheet.
+///
+/// The colors of all theme elements are set as custom properties on `:root`, named after the
+/// element (e.g., `--hl-ui-bg` for `ui.bg`). Every highlight group with attributes gets a
+/// `.hl-<group>` class referring to these properties. By default, the properties take the
+/// colors of the configured background, and those of the inverse background under a
+/// `prefers-color-scheme` media query. If `background` is set, only that theme is compiled.
+///
+/// Unless `gamut` is [`Gamut::Srgb`], the colors are also written as `oklch()` colors in the
+/// given gamut, overriding the sRGB colors in browsers that support them.
+pub fn compile(
+    colorscheme: &Colorscheme,
+    background: Option<Kind>,
+    gamut: Gamut,
+) -> Result<String, Error> {
+    finish(compile_program(colorscheme, background, gamut))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, Gamut};
+    use crate::configuration::Kind;
+
+    #[test]
+    fn style_sheet() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let string = |background| {
+            let color = colorscheme
+                .theme(background)
+                .get_color_by_name("syn.string");
+            format!("--hl-syn-string: {};", color.unwrap())
+        };
+
+        let program = compile(&colorscheme, None, Gamut::Srgb).unwrap();
+        assert_eq!(program.matches('{').count(), program.matches('}').count());
+        assert!(!program.contains("@supports"));
+        // the light theme by default, and the dark theme under the media query
+        let (light, dark) = program
+            .split_once("@media (prefers-color-scheme: dark) {")
+            .unwrap();
+        assert!(light.contains("color-scheme: light dark;"));
+        assert!(light.contains(&string(Kind::Light)));
+        assert!(dark.contains(&string(Kind::Dark)));
+        // `@lsp.type.string` links to `@string`
+        assert!(program.contains(".hl-\\@lsp\\.type\\.string {\n  color: var(--hl-syn-string);\n}"));
+
+        let program = compile(&colorscheme, Some(Kind::Dark), Gamut::Srgb).unwrap();
+        assert!(!program.contains("@media"));
+        assert!(program.contains("color-scheme: dark;"));
+        assert!(program.contains(&string(Kind::Dark)));
+    }
+
+    #[test]
+    fn wide_gamut() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        // the custom properties set to `oklch()` colors, with their chroma
+        let chromas = |gamut| {
+            let program = compile(&colorscheme, None, gamut).unwrap();
+            program
+                .lines()
+                .filter_map(|line| {
+                    let (property, color) = line.trim().split_once(": oklch(")?;
+                    let chroma = color.split(' ').nth(1)?.parse::<f32>().ok()?;
+                    Some((property.to_owned(), chroma))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let program = compile(&colorscheme, None, Gamut::Unbounded).unwrap();
+        let (srgb, oklch) = program
+            .split_once("@supports (color: oklch(0 0 0)) {")
+            .unwrap();
+        assert!(!srgb.contains("oklch("));
+        // the configured colors, for both backgrounds
+        assert!(oklch.contains("  :root {\n    --hl-diagnostics-error: oklch(0.36 0.1 15);"));
+        assert!(oklch.contains("  @media (prefers-color-scheme: dark) {\n    :root {"));
+
+        // chromas are positive, and Display-P3 colors at most as chromatic as the configured colors
+        let unbounded = chromas(Gamut::Unbounded);
+        let display_p3 = chromas(Gamut::DisplayP3);
+        assert_eq!(unbounded.len(), display_p3.len());
+        for ((property, unbounded), (p3_property, display_p3)) in unbounded.iter().zip(&display_p3)
+        {
+            assert_eq!(property, p3_property);
+            assert!(*display_p3 >= 0. && display_p3 <= &(unbounded + 1e-4));
+        }
+
+        assert!(chromas(Gamut::Srgb).is_empty());
+    }
+}
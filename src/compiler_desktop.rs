@@ -0,0 +1,372 @@
+//! Desktop and window manager theme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::compiler_common::finish;
+use crate::configuration::Kind;
+use crate::error::Error;
+
+/// A desktop component to compile a theme for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// A Waybar style sheet, defining the colors and styling the bar.
+    Waybar,
+    /// sway or i3 `client.*` color settings, to be included in their configuration.
+    Sway,
+    /// A rofi theme.
+    Rofi,
+    /// dunst frame and urgency sections, to be included in `dunstrc`.
+    Dunst,
+}
+
+/// The default mapping of the slots onto theme elements. This can be overridden in the
+/// configuration's `[desktop]` section.
+const SLOTS: [(&str, &str); 11] = [
+    ("background", "ui.bg"),
+    ("foreground", "ui.fg"),
+    ("foreground_dim", "ui.fg_dim"),
+    // bars, and windows and entries that are not focused
+    ("surface", "ui.bg_gutter"),
+    ("selection", "ui.bg_visual"),
+    ("border", "ui.float_border"),
+    // focused windows, and prompts
+    ("accent", "ui.special"),
+    ("urgent", "diagnostics.error"),
+    ("warning", "diagnostics.warning"),
+    ("info", "diagnostics.info"),
+    ("ok", "diagnostics.ok"),
+];
+
+/// Whether `name` names a desktop theme slot.
+pub(crate) fn is_slot(name: &str) -> bool {
+    SLOTS.iter().any(|(slot, _)| *slot == name)
+}
+
+struct Compiler {
+    name: String,
+    colors: Vec<(&'static str, Rgb8)>,
+    program: String,
+}
+
+impl Compiler {
+    fn color(&self, slot: &str) -> Rgb8 {
+        self.colors
+            .iter()
+            .find(|(name, _)| *name == slot)
+            .map(|&(_, color)| color)
+            .expect("all slots are resolved")
+    }
+
+    fn compile_waybar(&mut self) -> Result<()> {
+        writeln!(self.program, "/* {} */", self.name)?;
+        writeln!(self.program, "/* Generated by hi.nvim.rs. */")?;
+        writeln!(self.program)?;
+        for &(slot, color) in &self.colors {
+            writeln!(self.program, "@define-color {slot} {color};")?;
+        }
+        writeln!(self.program)?;
+        writeln!(
+            self.program,
+            r#"window#waybar {{
+  background-color: @surface;
+  color: @foreground;
+}}
+
+tooltip {{
+  background-color: @background;
+  border: 1px solid @border;
+}}
+
+#workspaces button {{
+  color: @foreground_dim;
+}}
+
+#workspaces button.focused,
+#workspaces button.active {{
+  background-color: @selection;
+  color: @foreground;
+}}
+
+#workspaces button.urgent {{
+  color: @urgent;
+}}
+
+.warning {{
+  color: @warning;
+}}
+
+.critical {{
+  color: @urgent;
+}}"#
+        )?;
+        Ok(())
+    }
+
+    fn compile_sway(&mut self) -> Result<()> {
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program, "# Generated by hi.nvim.rs.")?;
+        writeln!(self.program)?;
+        // border, background, text, indicator and child border
+        let classes = [
+            (
+                "focused",
+                ["accent", "selection", "foreground", "accent", "accent"],
+            ),
+            (
+                "focused_inactive",
+                ["surface", "surface", "foreground", "surface", "surface"],
+            ),
+            (
+                "unfocused",
+                [
+                    "surface",
+                    "background",
+                    "foreground_dim",
+                    "surface",
+                    "surface",
+                ],
+            ),
+            (
+                "urgent",
+                ["urgent", "urgent", "background", "urgent", "urgent"],
+            ),
+            (
+                "placeholder",
+                [
+                    "background",
+                    "background",
+                    "foreground",
+                    "background",
+                    "background",
+                ],
+            ),
+        ];
+        for (class, slots) in classes {
+            let colors: Vec<_> = slots
+                .iter()
+                .map(|&slot| self.color(slot).to_string())
+                .collect();
+            writeln!(self.program, "client.{class:<16} {}", colors.join(" "))?;
+        }
+        writeln!(
+            self.program,
+            "client.{:<16} {}",
+            "background",
+            self.color("background")
+        )?;
+        Ok(())
+    }
+
+    fn compile_rofi(&mut self) -> Result<()> {
+        writeln!(self.program, "/* {} */", self.name)?;
+        writeln!(self.program, "/* Generated by hi.nvim.rs. */")?;
+        writeln!(self.program)?;
+        writeln!(self.program, "* {{")?;
+        for &(slot, color) in &self.colors {
+            // prefixed, so the variables do not shadow rofi's properties (e.g., `border`)
+            writeln!(self.program, "  hl-{}: {color};", slot.replace('_', "-"))?;
+        }
+        writeln!(self.program)?;
+        writeln!(self.program, "  background-color: transparent;")?;
+        writeln!(self.program, "  text-color: @hl-foreground;")?;
+        writeln!(self.program, "}}")?;
+        writeln!(self.program)?;
+        writeln!(
+            self.program,
+            r#"window {{
+  background-color: @hl-background;
+  border: 2px;
+  border-color: @hl-border;
+}}
+
+prompt {{
+  text-color: @hl-accent;
+}}
+
+inputbar {{
+  background-color: @hl-surface;
+}}
+
+element normal.normal,
+element alternate.normal {{
+  text-color: @hl-foreground-dim;
+}}
+
+element normal.urgent,
+element alternate.urgent {{
+  text-color: @hl-urgent;
+}}
+
+element normal.active,
+element alternate.active {{
+  text-color: @hl-info;
+}}
+
+element selected.normal {{
+  background-color: @hl-selection;
+  text-color: @hl-foreground;
+}}
+
+element selected.urgent {{
+  background-color: @hl-urgent;
+  text-color: @hl-background;
+}}
+
+element selected.active {{
+  background-color: @hl-info;
+  text-color: @hl-background;
+}}
+
+element-text,
+element-icon {{
+  text-color: inherit;
+}}"#
+        )?;
+        Ok(())
+    }
+
+    fn compile_dunst(&mut self) -> Result<()> {
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program, "# Generated by hi.nvim.rs.")?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[global]")?;
+        writeln!(
+            self.program,
+            "    frame_color = \"{}\"",
+            self.color("border")
+        )?;
+        writeln!(self.program, "    separator_color = frame")?;
+        // background, foreground, frame and progress bar
+        let urgencies = [
+            ("low", ["background", "foreground_dim", "border", "ok"]),
+            ("normal", ["background", "foreground", "accent", "info"]),
+            ("critical", ["background", "foreground", "urgent", "urgent"]),
+        ];
+        for (urgency, [background, foreground, frame, highlight]) in urgencies {
+            writeln!(self.program)?;
+            writeln!(self.program, "[urgency_{urgency}]")?;
+            writeln!(
+                self.program,
+                "    background = \"{}\"",
+                self.color(background)
+            )?;
+            writeln!(
+                self.program,
+                "    foreground = \"{}\"",
+                self.color(foreground)
+            )?;
+            writeln!(self.program, "    frame_color = \"{}\"", self.color(frame))?;
+            writeln!(
+                self.program,
+                "    highlight = \"{}\"",
+                self.color(highlight)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compile the light or dark theme of a color scheme to a theme for a desktop component. The
+/// theme is named `<name>-light` or `<name>-dark`.
+///
+/// All components share a set of slots taken from theme elements: `background`, `foreground`
+/// and `selection` from `ui.bg`, `ui.fg` and `ui.bg_visual`, `urgent` and the other states from
+/// the `diagnostics` elements, and so on. The mapping can be overridden in the configuration's
+/// `[desktop]` section.
+pub fn compile(
+    colorscheme: &Colorscheme,
+    component: Component,
+    background: Kind,
+) -> Result<String, Error> {
+    let theme = colorscheme.theme(background);
+    let colors = SLOTS
+        .iter()
+        .map(|&(slot, default)| {
+            let element = match colorscheme.desktop.get(slot) {
+                Some(&element) => format!("{element}"),
+                None => default.to_owned(),
+            };
+            theme
+                .get_color_by_name(&element)
+                .map(|color| (slot, color))
+                .ok_or(Error::ThemeElementMissing(element))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut compiler = Compiler {
+        name: format!("{}-{background}", colorscheme.name()),
+        colors,
+        program: String::with_capacity(2_048),
+    };
+
+    let result = match component {
+        Component::Waybar => compiler.compile_waybar(),
+        Component::Sway => compiler.compile_sway(),
+        Component::Rofi => compiler.compile_rofi(),
+        Component::Dunst => compiler.compile_dunst(),
+    };
+
+    finish(result.map(|()| compiler.program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, Component};
+    use crate::configuration::Kind;
+
+    #[test]
+    fn themes() {
+        let config = format!(
+            "{}\n[desktop]\naccent = \"diagnostics.info\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        for background in [Kind::Light, Kind::Dark] {
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                color.unwrap().to_string()
+            };
+            let theme = |component| compile(&colorscheme, component, background).unwrap();
+
+            // the variables referred to are those defined, and the override is honoured
+            let waybar = theme(Component::Waybar);
+            assert!(waybar.contains(&format!(
+                "@define-color accent {};",
+                color("diagnostics.info")
+            )));
+            for reference in waybar.split('@').skip(1) {
+                let name = reference.split([';', ' ']).next().unwrap();
+                assert!(
+                    name == "define-color" || waybar.contains(&format!("@define-color {name} "))
+                );
+            }
+
+            // rofi's variables are prefixed, so they do not shadow its properties
+            let rofi = theme(Component::Rofi);
+            assert!(rofi.contains(&format!("  hl-background: {};", color("ui.bg"))));
+            assert!(rofi.contains(&format!("  hl-accent: {};", color("diagnostics.info"))));
+            assert!(!rofi.contains("  border: #"));
+            for reference in rofi.split('@').skip(1) {
+                let name = reference.split([';', ' ']).next().unwrap();
+                assert!(name.starts_with("hl-"));
+                assert!(rofi.contains(&format!("  {name}: #")));
+            }
+            assert_eq!(rofi.matches('{').count(), rofi.matches('}').count());
+
+            let sway = theme(Component::Sway);
+            assert!(sway.contains(&format!(
+                "client.focused          {}",
+                color("diagnostics.info")
+            )));
+
+            let dunst = theme(Component::Dunst);
+            assert!(dunst.contains(&format!(
+                "[urgency_critical]\n    background = \"{}\"",
+                color("ui.bg")
+            )));
+        }
+    }
+}
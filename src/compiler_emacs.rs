@@ -0,0 +1,300 @@
+//! Emacs `deftheme` compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::Colorscheme;
+use crate::compiler_common::finish;
+use crate::configuration::{Highlight, Kind};
+use crate::error::Error;
+
+/// The default mapping of Emacs faces onto highlight groups and tree-sitter captures. This can be
+/// overridden in the configuration's `[scopes.emacs]` section.
+const FACES: &[(&str, &str)] = &[
+    // UI
+    ("default", "Normal"),
+    ("cursor", "Cursor"),
+    ("error", "ErrorMsg"),
+    ("fringe", "SignColumn"),
+    ("header-line", "WinBar"),
+    ("highlight", "CursorLine"),
+    ("hl-line", "CursorLine"),
+    ("isearch", "CurSearch"),
+    ("isearch-fail", "ErrorMsg"),
+    ("lazy-highlight", "Search"),
+    ("line-number", "LineNr"),
+    ("line-number-current-line", "CursorLineNr"),
+    ("link", "Underlined"),
+    ("match", "Search"),
+    ("minibuffer-prompt", "Question"),
+    ("mode-line", "StatusLine"),
+    ("mode-line-inactive", "StatusLineNC"),
+    ("query-replace", "Substitute"),
+    ("region", "Visual"),
+    ("shadow", "NonText"),
+    ("show-paren-match", "MatchParen"),
+    ("success", "DiagnosticOk"),
+    ("tooltip", "NormalFloat"),
+    ("vertical-border", "WinSeparator"),
+    ("warning", "DiagnosticWarn"),
+    ("whitespace-space", "Whitespace"),
+    ("whitespace-tab", "Whitespace"),
+    // Syntax
+    ("font-lock-bracket-face", "@punctuation.bracket"),
+    ("font-lock-builtin-face", "@function.builtin"),
+    ("font-lock-comment-delimiter-face", "@comment"),
+    ("font-lock-comment-face", "@comment"),
+    ("font-lock-constant-face", "@constant"),
+    ("font-lock-delimiter-face", "@punctuation.delimiter"),
+    ("font-lock-doc-face", "@comment.documentation"),
+    ("font-lock-escape-face", "@string.escape"),
+    ("font-lock-function-call-face", "@function.call"),
+    ("font-lock-function-name-face", "@function"),
+    ("font-lock-keyword-face", "@keyword"),
+    ("font-lock-negation-char-face", "@operator"),
+    ("font-lock-number-face", "@number"),
+    ("font-lock-operator-face", "@operator"),
+    ("font-lock-preprocessor-face", "@keyword.directive"),
+    ("font-lock-property-name-face", "@property"),
+    ("font-lock-property-use-face", "@property"),
+    ("font-lock-punctuation-face", "@punctuation"),
+    ("font-lock-regexp-face", "@string.regexp"),
+    ("font-lock-string-face", "@string"),
+    ("font-lock-type-face", "@type"),
+    ("font-lock-variable-name-face", "@variable"),
+    ("font-lock-variable-use-face", "@variable"),
+    ("font-lock-warning-face", "DiagnosticWarn"),
+    // Diffs
+    ("diff-added", "DiffAdd"),
+    ("diff-changed", "DiffChange"),
+    ("diff-file-header", "Title"),
+    ("diff-indicator-added", "diffAdded"),
+    ("diff-indicator-changed", "diffChanged"),
+    ("diff-indicator-removed", "diffRemoved"),
+    ("diff-refine-added", "DiffText"),
+    ("diff-refine-changed", "DiffText"),
+    ("diff-refine-removed", "DiffText"),
+    ("diff-removed", "DiffDelete"),
+    // Diagnostics
+    ("flycheck-error", "DiagnosticUnderlineError"),
+    ("flycheck-fringe-error", "DiagnosticSignError"),
+    ("flycheck-fringe-info", "DiagnosticSignInfo"),
+    ("flycheck-fringe-warning", "DiagnosticSignWarn"),
+    ("flycheck-info", "DiagnosticUnderlineInfo"),
+    ("flycheck-warning", "DiagnosticUnderlineWarn"),
+    ("flymake-error", "DiagnosticUnderlineError"),
+    ("flymake-error-echo", "DiagnosticError"),
+    ("flymake-note", "DiagnosticUnderlineInfo"),
+    ("flymake-note-echo", "DiagnosticInfo"),
+    ("flymake-warning", "DiagnosticUnderlineWarn"),
+    ("flymake-warning-echo", "DiagnosticWarn"),
+];
+
+struct Compiler<'c> {
+    colorscheme: &'c Colorscheme<'c>,
+    program: String,
+    indent: u8,
+}
+
+impl Compiler<'_> {
+    fn indent(&mut self) {
+        self.indent = self.indent.saturating_add(1);
+    }
+
+    fn dedent(&mut self) {
+        self.indent = self.indent.saturating_sub(1);
+    }
+
+    fn write_indent(&mut self) {
+        self.program += &str::repeat(" ", usize::from(self.indent));
+    }
+
+    /// The face attributes of a highlight in the light or dark theme, as a property list.
+    fn face_attributes(&self, highlight: &Highlight, background: Kind) -> Result<String> {
+        let theme = self.colorscheme.theme(background);
+        let color = |element| {
+            theme
+                .get_color(element)
+                .map(|color| format!(r#""{color}""#))
+        };
+        let sp = highlight
+            .sp
+            .and_then(color)
+            .unwrap_or_else(|| "t".to_owned());
+
+        let mut attributes = String::new();
+        if let Some(fg) = highlight.fg.and_then(color) {
+            write!(attributes, " :foreground {fg}")?;
+        }
+        if let Some(bg) = highlight.bg.and_then(color) {
+            write!(attributes, " :background {bg}")?;
+        }
+        if highlight.bold.unwrap_or(false) {
+            write!(attributes, " :weight bold")?;
+        }
+        if highlight.italic.unwrap_or(false) {
+            write!(attributes, " :slant italic")?;
+        }
+        if highlight.undercurl.unwrap_or(false) {
+            write!(attributes, " :underline (:style wave :color {sp})")?;
+        } else if [
+            highlight.underline,
+            highlight.underdouble,
+            highlight.underdotted,
+            highlight.underdashed,
+        ]
+        .into_iter()
+        .any(|set| set.unwrap_or(false))
+        {
+            write!(attributes, " :underline {sp}")?;
+        }
+        if highlight.strikethrough.unwrap_or(false) {
+            write!(attributes, " :strike-through t")?;
+        }
+        if highlight.reverse.unwrap_or(false) {
+            write!(attributes, " :inverse-video t")?;
+        }
+
+        Ok(attributes)
+    }
+
+    fn compile_face(&mut self, face: &str, group: &str, backgrounds: &[Kind]) -> Result<()> {
+        let Some(highlight) = captures::resolve(self.colorscheme, group) else {
+            return Ok(());
+        };
+
+        let mut specs = Vec::new();
+        for &background in backgrounds {
+            let attributes = self.face_attributes(&highlight, background)?;
+            if attributes.is_empty() {
+                continue;
+            }
+            let display = match backgrounds {
+                [_] => "((class color))".to_owned(),
+                _ => format!("((class color) (background {background}))"),
+            };
+            specs.push(format!("({display}{attributes})"));
+        }
+        if specs.is_empty() {
+            return Ok(());
+        }
+
+        self.write_indent();
+        let prefix = format!("'({face} (");
+        write!(self.program, "{prefix}")?;
+        for (i, spec) in specs.iter().enumerate() {
+            if i > 0 {
+                writeln!(self.program)?;
+                self.write_indent();
+                self.program += &str::repeat(" ", prefix.len());
+            }
+            write!(self.program, "{spec}")?;
+        }
+        writeln!(self.program, "))")?;
+
+        Ok(())
+    }
+
+    fn compile(&mut self, name: &str, backgrounds: &[Kind]) -> Result<()> {
+        writeln!(
+            self.program,
+            ";;; {name}-theme.el --- The {name} color theme  -*- lexical-binding: t -*-"
+        )?;
+        writeln!(self.program)?;
+        writeln!(self.program, ";; Generated by hi.nvim.rs.")?;
+        writeln!(self.program)?;
+        writeln!(self.program, ";;; Code:")?;
+        writeln!(self.program)?;
+        writeln!(self.program, "(deftheme {name})")?;
+        writeln!(self.program)?;
+        writeln!(self.program, "(custom-theme-set-faces")?;
+        self.indent();
+        self.write_indent();
+        writeln!(self.program, "'{name}")?;
+
+        for (face, group) in captures::scope_mapping(self.colorscheme, "emacs", FACES) {
+            self.compile_face(face, group, backgrounds)?;
+        }
+        // close `custom-theme-set-faces`
+        self.program.truncate(self.program.trim_end().len());
+        writeln!(self.program, ")")?;
+        self.dedent();
+
+        writeln!(self.program)?;
+        writeln!(self.program, "(provide-theme '{name})")?;
+        writeln!(self.program)?;
+        writeln!(self.program, ";;; {name}-theme.el ends here")?;
+
+        Ok(())
+    }
+}
+
+/// Compile a color scheme to an Emacs theme, to be placed in `<name>-theme.el` on the
+/// `custom-theme-load-path`.
+///
+/// Highlights are mapped onto Emacs faces, following links to their final groups. The mapping
+/// can be overridden in the configuration's `[scopes.emacs]` section. By default, the light and
+/// dark theme are both compiled, using `((class color) (background light))` and `((class color)
+/// (background dark))` face specs. If `background` is set, only that theme is compiled, named
+/// `<name>-light` or `<name>-dark`.
+pub fn compile(colorscheme: &Colorscheme, background: Option<Kind>) -> Result<String, Error> {
+    let (name, backgrounds) = match background {
+        Some(background) => (
+            format!("{}-{background}", colorscheme.name()),
+            vec![background],
+        ),
+        None => (colorscheme.name().to_owned(), vec![Kind::Light, Kind::Dark]),
+    };
+
+    let mut compiler = Compiler {
+        colorscheme,
+        program: String::with_capacity(16_384),
+        indent: 0,
+    };
+    let result = compiler.compile(&name, &backgrounds);
+
+    finish(result.map(|()| compiler.program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn theme() {
+        let config = format!(
+            "{}\n[scopes.emacs]\n\"font-lock-keyword-face\" = \"Comment\"\n\"error\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let program = compile(&colorscheme, None).unwrap();
+        // the face spec of a face, from its name up to the next face
+        let face = |name: &str| {
+            let (_, spec) = program.split_once(&format!(" '({name} ("))?;
+            Some(spec.split(" '(").next().unwrap())
+        };
+        let foreground = |element, background: Kind| {
+            let color = colorscheme.theme(background).get_color_by_name(element);
+            format!(
+                "(((class color) (background {background})) :foreground \"{}\"",
+                color.unwrap()
+            )
+        };
+
+        assert!(program.contains("(deftheme highlow)"));
+        assert!(program.ends_with("(provide-theme 'highlow)\n\n;;; highlow-theme.el ends here"));
+        assert_eq!(program.matches('(').count(), program.matches(')').count());
+        for background in [Kind::Light, Kind::Dark] {
+            // `@keyword.directive` falls back to `PreProc`
+            let preprocessor = face("font-lock-preprocessor-face").unwrap();
+            assert!(preprocessor.contains(&foreground("syn.preproc", background)));
+            // the overrides
+            let keyword = face("font-lock-keyword-face").unwrap();
+            assert!(keyword.contains(&foreground("syn.comment", background)));
+        }
+        assert!(face("error").is_none());
+    }
+}
@@ -0,0 +1,240 @@
+//! Helix theme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Theme};
+use crate::compiler_common::finish;
+use crate::configuration::{Highlight, Kind};
+use crate::error::Error;
+
+/// The default mapping of Helix scopes onto highlight groups and tree-sitter captures. This can be
+/// overridden in the configuration's `[scopes.helix]` section.
+const SCOPES: &[(&str, &str)] = &[
+    // syntax
+    ("attribute", "@attribute"),
+    ("comment", "@comment"),
+    ("constant", "@constant"),
+    ("constant.builtin", "@constant.builtin"),
+    ("constant.builtin.boolean", "@boolean"),
+    ("constant.character", "@character"),
+    ("constant.character.escape", "@string.escape"),
+    ("constant.numeric", "@number"),
+    ("constant.numeric.float", "@number.float"),
+    ("constructor", "@constructor"),
+    ("function", "@function"),
+    ("function.builtin", "@function.builtin"),
+    ("function.macro", "@function.macro"),
+    ("function.method", "@function.method"),
+    ("keyword", "@keyword"),
+    ("keyword.control.conditional", "@keyword.conditional"),
+    ("keyword.control.exception", "@keyword.exception"),
+    ("keyword.control.import", "@keyword.import"),
+    ("keyword.control.repeat", "@keyword.repeat"),
+    ("keyword.control.return", "@keyword.return"),
+    ("keyword.directive", "@keyword.directive"),
+    ("keyword.function", "@keyword.function"),
+    ("keyword.operator", "@keyword.operator"),
+    ("keyword.storage", "@keyword.modifier"),
+    ("keyword.storage.type", "@keyword.type"),
+    ("label", "@label"),
+    ("namespace", "@module"),
+    ("operator", "@operator"),
+    ("punctuation", "@punctuation"),
+    ("punctuation.bracket", "@punctuation.bracket"),
+    ("punctuation.delimiter", "@punctuation.delimiter"),
+    ("punctuation.special", "@punctuation.special"),
+    ("special", "Special"),
+    ("string", "@string"),
+    ("string.regexp", "@string.regexp"),
+    ("string.special", "@string.special"),
+    ("tag", "@tag"),
+    ("type", "@type"),
+    ("type.builtin", "@type.builtin"),
+    ("variable", "@variable"),
+    ("variable.builtin", "@variable.builtin"),
+    ("variable.other.member", "@variable.member"),
+    ("variable.parameter", "@variable.parameter"),
+    // markup
+    ("markup.bold", "@markup.strong"),
+    ("markup.heading", "@markup.heading"),
+    ("markup.italic", "@markup.italic"),
+    ("markup.link.text", "@markup.link.label"),
+    ("markup.link.url", "@markup.link.url"),
+    ("markup.list", "@markup.list"),
+    ("markup.quote", "@markup.quote"),
+    ("markup.raw", "@markup.raw"),
+    ("markup.strikethrough", "@markup.strikethrough"),
+    // diffs
+    ("diff.delta", "diffChanged"),
+    ("diff.minus", "diffRemoved"),
+    ("diff.plus", "diffAdded"),
+    // interface
+    ("ui.background", "Normal"),
+    ("ui.cursor", "Cursor"),
+    ("ui.cursor.match", "MatchParen"),
+    ("ui.cursorline.primary", "CursorLine"),
+    ("ui.gutter", "SignColumn"),
+    ("ui.help", "NormalFloat"),
+    ("ui.linenr", "LineNr"),
+    ("ui.linenr.selected", "CursorLineNr"),
+    ("ui.menu", "Pmenu"),
+    ("ui.menu.selected", "PmenuSel"),
+    ("ui.popup", "NormalFloat"),
+    ("ui.selection", "Visual"),
+    ("ui.statusline", "StatusLine"),
+    ("ui.statusline.inactive", "StatusLineNC"),
+    ("ui.text", "Normal"),
+    ("ui.virtual.indent-guide", "IblIndent"),
+    ("ui.virtual.ruler", "ColorColumn"),
+    ("ui.virtual.whitespace", "Whitespace"),
+    ("ui.window", "WinSeparator"),
+    // diagnostics
+    ("diagnostic.error", "DiagnosticUnderlineError"),
+    ("diagnostic.hint", "DiagnosticUnderlineHint"),
+    ("diagnostic.info", "DiagnosticUnderlineInfo"),
+    ("diagnostic.warning", "DiagnosticUnderlineWarn"),
+    ("error", "DiagnosticError"),
+    ("hint", "DiagnosticHint"),
+    ("info", "DiagnosticInfo"),
+    ("warning", "DiagnosticWarn"),
+];
+
+struct Compiler<'c> {
+    colorscheme: &'c Colorscheme<'c>,
+    program: String,
+}
+
+impl Compiler<'_> {
+    /// Write a Helix style. Nothing is written if the highlight has no attributes Helix supports.
+    fn compile_style(&mut self, scope: &str, highlight: &Highlight) -> Result<()> {
+        let mut attributes = Vec::new();
+        if let Some(fg) = highlight.fg {
+            attributes.push(format!(r#"fg = "{fg}""#));
+        }
+        if let Some(bg) = highlight.bg {
+            attributes.push(format!(r#"bg = "{bg}""#));
+        }
+
+        let underline_style = [
+            (highlight.underline, "line"),
+            (highlight.undercurl, "curl"),
+            (highlight.underdouble, "double_line"),
+            (highlight.underdotted, "dotted"),
+            (highlight.underdashed, "dashed"),
+        ]
+        .into_iter()
+        .find_map(|(set, style)| set.unwrap_or(false).then_some(style));
+        if let Some(style) = underline_style {
+            match highlight.sp {
+                Some(sp) => attributes.push(format!(
+                    r#"underline = {{ color = "{sp}", style = "{style}" }}"#
+                )),
+                None => attributes.push(format!(r#"underline = {{ style = "{style}" }}"#)),
+            }
+        }
+
+        let modifiers = [
+            (highlight.bold, "bold"),
+            (highlight.italic, "italic"),
+            (highlight.strikethrough, "crossed_out"),
+            (highlight.reverse, "reversed"),
+        ]
+        .into_iter()
+        .filter(|(set, _)| set.unwrap_or(false))
+        .map(|(_, modifier)| format!(r#""{modifier}""#))
+        .collect::<Vec<_>>();
+        if !modifiers.is_empty() {
+            attributes.push(format!("modifiers = [{}]", modifiers.join(", ")));
+        }
+
+        if attributes.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(
+            self.program,
+            r#""{scope}" = {{ {} }}"#,
+            attributes.join(", ")
+        )?;
+
+        Ok(())
+    }
+
+    fn compile(&mut self, background: Kind) -> Result<()> {
+        writeln!(self.program, "# {}-{background}\n", self.colorscheme.name())?;
+
+        for (scope, group) in captures::scope_mapping(self.colorscheme, "helix", SCOPES) {
+            if let Some(highlight) = captures::resolve(self.colorscheme, group) {
+                self.compile_style(scope, &highlight)?;
+            }
+        }
+
+        writeln!(self.program)?;
+        self.compile_palette(self.colorscheme.theme(background))
+    }
+
+    fn compile_palette(&mut self, theme: &Theme) -> Result<()> {
+        writeln!(self.program, "[palette]")?;
+        for (element, color) in theme.elements() {
+            writeln!(self.program, r#""{element}" = "{color}""#)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compile the light or dark theme of a color scheme to a Helix theme. The highlights are mapped
+/// onto Helix scopes. The theme's colors are written to the palette, named after their theme
+/// elements (e.g., `syn.keyword`).
+pub fn compile(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    let mut compiler = Compiler {
+        colorscheme,
+        program: String::with_capacity(8192),
+    };
+    let result = compiler.compile(background);
+
+    finish(result.map(|()| compiler.program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn theme() {
+        let config = format!(
+            "{}\n[scopes.helix]\n\"keyword\" = \"Comment\"\n\"attribute\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+
+        let mut palettes = Vec::new();
+        for background in [Kind::Light, Kind::Dark] {
+            let theme: toml::Value = compile(&colorscheme, background).unwrap().parse().unwrap();
+
+            // `@keyword.conditional` falls back to `Conditional`, which links to `Statement`
+            assert_eq!(
+                theme["keyword.control.conditional"]["fg"].as_str(),
+                Some("syn.statement")
+            );
+            // the overrides
+            assert_eq!(theme["keyword"]["fg"].as_str(), Some("syn.comment"));
+            assert!(theme.get("attribute").is_none());
+
+            let palette = &theme["palette"];
+            let color = colorscheme
+                .theme(background)
+                .get_color_by_name("syn.statement");
+            assert_eq!(
+                palette["syn.statement"].as_str(),
+                color.map(|color| color.to_string()).as_deref()
+            );
+            palettes.push(palette.clone());
+        }
+        assert_ne!(palettes[0], palettes[1]);
+    }
+}
@@ -0,0 +1,307 @@
+//! JetBrains editor color scheme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::compiler_common::finish;
+use crate::compiler_textmate::escape_xml;
+use crate::configuration::Kind;
+use crate::error::Error;
+
+/// The default mapping of the editor colors onto highlight groups. These take the background
+/// color of their group, unless listed in [`FOREGROUND_COLORS`].
+const EDITOR_COLORS: &[(&str, &str)] = &[
+    ("ADDED_LINES_COLOR", "GitSignsAdd"),
+    ("CARET_COLOR", "Cursor"),
+    ("CARET_ROW_COLOR", "CursorLine"),
+    ("CONSOLE_BACKGROUND_KEY", "Normal"),
+    ("DELETED_LINES_COLOR", "GitSignsDelete"),
+    ("DOCUMENTATION_COLOR", "NormalFloat"),
+    ("GUTTER_BACKGROUND", "SignColumn"),
+    ("INDENT_GUIDE", "IblIndent"),
+    ("LINE_NUMBERS_COLOR", "LineNr"),
+    ("LINE_NUMBER_ON_CARET_ROW_COLOR", "CursorLineNr"),
+    ("LOOKUP_COLOR", "Pmenu"),
+    ("METHOD_SEPARATORS_COLOR", "WinSeparator"),
+    ("MODIFIED_LINES_COLOR", "GitSignsChange"),
+    ("RIGHT_MARGIN_COLOR", "ColorColumn"),
+    ("SELECTED_INDENT_GUIDE", "IblScope"),
+    ("SELECTION_BACKGROUND", "Visual"),
+    ("SELECTION_FOREGROUND", "Visual"),
+    ("WHITESPACES", "Whitespace"),
+];
+
+/// The editor colors that take the foreground color of their group.
+const FOREGROUND_COLORS: &[&str] = &[
+    "ADDED_LINES_COLOR",
+    "DELETED_LINES_COLOR",
+    "INDENT_GUIDE",
+    "LINE_NUMBERS_COLOR",
+    "LINE_NUMBER_ON_CARET_ROW_COLOR",
+    "METHOD_SEPARATORS_COLOR",
+    "MODIFIED_LINES_COLOR",
+    "SELECTED_INDENT_GUIDE",
+    "SELECTION_FOREGROUND",
+    "WHITESPACES",
+];
+
+/// The default mapping of the attribute keys onto highlight groups and tree-sitter captures.
+const ATTRIBUTES: &[(&str, &str)] = &[
+    // editor
+    ("DIFF_DELETED", "DiffDelete"),
+    ("DIFF_INSERTED", "DiffAdd"),
+    ("DIFF_MODIFIED", "DiffChange"),
+    ("ERRORS_ATTRIBUTES", "DiagnosticUnderlineError"),
+    ("INFO_ATTRIBUTES", "DiagnosticUnderlineInfo"),
+    ("INLAY_DEFAULT", "LspInlayHint"),
+    ("MATCHED_BRACE_ATTRIBUTES", "MatchParen"),
+    ("SEARCH_RESULT_ATTRIBUTES", "Search"),
+    ("TEXT", "Normal"),
+    ("TEXT_SEARCH_RESULT_ATTRIBUTES", "Search"),
+    ("TODO_DEFAULT_ATTRIBUTES", "Todo"),
+    ("TYPO", "SpellBad"),
+    ("WARNING_ATTRIBUTES", "DiagnosticUnderlineWarn"),
+    // language defaults
+    ("DEFAULT_ATTRIBUTE", "@tag.attribute"),
+    ("DEFAULT_BLOCK_COMMENT", "Comment"),
+    ("DEFAULT_BRACES", "@punctuation.bracket"),
+    ("DEFAULT_BRACKETS", "@punctuation.bracket"),
+    ("DEFAULT_CLASS_NAME", "@type"),
+    ("DEFAULT_CLASS_REFERENCE", "@type"),
+    ("DEFAULT_COMMA", "@punctuation.delimiter"),
+    ("DEFAULT_CONSTANT", "Constant"),
+    ("DEFAULT_DOC_COMMENT", "@comment.documentation"),
+    ("DEFAULT_DOC_COMMENT_TAG", "SpecialComment"),
+    ("DEFAULT_DOT", "@punctuation.delimiter"),
+    ("DEFAULT_FUNCTION_CALL", "@function.call"),
+    ("DEFAULT_FUNCTION_DECLARATION", "Function"),
+    ("DEFAULT_GLOBAL_VARIABLE", "@variable"),
+    ("DEFAULT_IDENTIFIER", "@variable"),
+    ("DEFAULT_INSTANCE_FIELD", "@variable.member"),
+    ("DEFAULT_INSTANCE_METHOD", "@function.method"),
+    ("DEFAULT_INTERFACE_NAME", "@type"),
+    ("DEFAULT_INVALID_STRING_ESCAPE", "Error"),
+    ("DEFAULT_KEYWORD", "Keyword"),
+    ("DEFAULT_LABEL", "@label"),
+    ("DEFAULT_LINE_COMMENT", "Comment"),
+    ("DEFAULT_LOCAL_VARIABLE", "@variable"),
+    ("DEFAULT_METADATA", "@attribute"),
+    ("DEFAULT_NUMBER", "Number"),
+    ("DEFAULT_OPERATION_SIGN", "Operator"),
+    ("DEFAULT_PARAMETER", "@variable.parameter"),
+    ("DEFAULT_PARENTHS", "@punctuation.bracket"),
+    ("DEFAULT_PREDEFINED_SYMBOL", "@function.builtin"),
+    ("DEFAULT_SEMICOLON", "@punctuation.delimiter"),
+    ("DEFAULT_STATIC_FIELD", "@variable.member"),
+    ("DEFAULT_STATIC_METHOD", "@function.method"),
+    ("DEFAULT_STRING", "String"),
+    ("DEFAULT_TAG", "@tag"),
+    ("DEFAULT_VALID_STRING_ESCAPE", "@string.escape"),
+];
+
+/// The `EFFECT_TYPE` values of JetBrains' text attributes.
+const EFFECT_LINE_UNDERSCORE: u8 = 1;
+const EFFECT_WAVE_UNDERSCORE: u8 = 2;
+const EFFECT_STRIKEOUT: u8 = 3;
+const EFFECT_BOLD_DOTTED_LINE: u8 = 5;
+
+/// A color in the notation of color schemes: hexadecimal, without the leading `#`.
+fn hex(color: Rgb8) -> String {
+    let Rgb8 { red, green, blue } = color;
+    format!("{red:02x}{green:02x}{blue:02x}")
+}
+
+fn compile_program(colorscheme: &Colorscheme, background: Kind) -> Result<String> {
+    let theme = colorscheme.theme(background);
+    let mut program = String::with_capacity(16_384);
+
+    // the bundled scheme of the same background provides whatever is not mapped
+    let parent = match background {
+        Kind::Light => "Default",
+        Kind::Dark => "Darcula",
+    };
+    writeln!(program, "<!-- Generated by hi.nvim.rs. -->")?;
+    writeln!(
+        program,
+        r#"<scheme name="{}" version="142" parent_scheme="{parent}">"#,
+        escape_xml(&format!("{}-{background}", colorscheme.name()))
+    )?;
+
+    // Overrides of attributes and editor colors share the `[scopes.jetbrains]` section. Keys
+    // that are not editor colors by default are attributes.
+    let mapping = captures::scope_mapping(
+        colorscheme,
+        "jetbrains",
+        &[EDITOR_COLORS, ATTRIBUTES].concat(),
+    );
+    let is_editor_color = |key: &str| EDITOR_COLORS.iter().any(|(color, _)| *color == key);
+
+    writeln!(program, "  <colors>")?;
+    for (&key, &group) in mapping.iter().filter(|(&key, _)| is_editor_color(key)) {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+        let color = match FOREGROUND_COLORS.contains(&key) {
+            true => highlight.fg,
+            false => highlight.bg,
+        };
+        if let Some(color) = color.and_then(|color| theme.get_color(color)) {
+            writeln!(
+                program,
+                r#"    <option name="{}" value="{}" />"#,
+                escape_xml(key),
+                hex(color)
+            )?;
+        }
+    }
+    writeln!(program, "  </colors>")?;
+
+    writeln!(program, "  <attributes>")?;
+    for (&key, &group) in mapping.iter().filter(|(&key, _)| !is_editor_color(key)) {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+
+        let mut options = Vec::new();
+        let (fg, bg) = match highlight.reverse.unwrap_or(false) {
+            true => (highlight.bg, highlight.fg),
+            false => (highlight.fg, highlight.bg),
+        };
+        if let Some(fg) = fg.and_then(|fg| theme.get_color(fg)) {
+            options.push(("FOREGROUND", hex(fg)));
+        }
+        if let Some(bg) = bg.and_then(|bg| theme.get_color(bg)) {
+            options.push(("BACKGROUND", hex(bg)));
+        }
+
+        let font_type = match (
+            highlight.bold.unwrap_or(false),
+            highlight.italic.unwrap_or(false),
+        ) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        if font_type != 0 {
+            options.push(("FONT_TYPE", font_type.to_string()));
+        }
+
+        let effect = [
+            (highlight.undercurl, EFFECT_WAVE_UNDERSCORE),
+            (highlight.underline, EFFECT_LINE_UNDERSCORE),
+            (highlight.underdouble, EFFECT_LINE_UNDERSCORE),
+            (highlight.underdashed, EFFECT_LINE_UNDERSCORE),
+            (highlight.underdotted, EFFECT_BOLD_DOTTED_LINE),
+            (highlight.strikethrough, EFFECT_STRIKEOUT),
+        ]
+        .into_iter()
+        .find_map(|(set, effect)| set.unwrap_or(false).then_some(effect));
+        if let Some(effect) = effect {
+            // effects are drawn in the foreground color, unless a special color is given
+            let color = highlight
+                .sp
+                .or(highlight.fg)
+                .or_else(|| colorscheme.highlight("Normal").and_then(|normal| normal.fg));
+            if let Some(color) = color.and_then(|color| theme.get_color(color)) {
+                options.push(("EFFECT_COLOR", hex(color)));
+                options.push(("EFFECT_TYPE", effect.to_string()));
+            }
+        }
+
+        if options.is_empty() {
+            continue;
+        }
+        writeln!(program, r#"    <option name="{}">"#, escape_xml(key))?;
+        writeln!(program, "      <value>")?;
+        for (name, value) in options {
+            writeln!(
+                program,
+                r#"        <option name="{name}" value="{value}" />"#
+            )?;
+        }
+        writeln!(program, "      </value>")?;
+        writeln!(program, "    </option>")?;
+    }
+    writeln!(program, "  </attributes>")?;
+    writeln!(program, "</scheme>")?;
+
+    Ok(program)
+}
+
+/// Compile the light or dark theme of a color scheme to a JetBrains editor color scheme
+/// (`.icls`), which can be imported in IntelliJ IDEA and other JetBrains IDEs.
+///
+/// Highlights are mapped onto the language defaults' attribute keys (e.g., `DEFAULT_KEYWORD`)
+/// and the editor's colors and attributes (e.g., `CARET_ROW_COLOR` and `DIFF_INSERTED`),
+/// following links to their final groups. The mapping can be overridden in the configuration's
+/// `[scopes.jetbrains]` section. Whatever is not mapped is taken from the bundled `Default` or
+/// `Darcula` scheme.
+pub fn compile(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    finish(compile_program(colorscheme, background))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn scheme() {
+        let config = format!(
+            "{}\n[scopes.jetbrains]\nDEFAULT_KEYWORD = \"Comment\"\nCARET_ROW_COLOR = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        for (background, parent) in [(Kind::Light, "Default"), (Kind::Dark, "Darcula")] {
+            let program = compile(&colorscheme, background).unwrap();
+            // colors are written without the leading `#`
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                color.unwrap().to_string().replace('#', "")
+            };
+            // the foreground of an attribute
+            let foreground = |key: &str| {
+                let (_, attribute) = program.split_once(&format!("<option name=\"{key}\">"))?;
+                let (attribute, _) = attribute.split_once("</option>")?;
+                let (_, foreground) =
+                    attribute.split_once("<option name=\"FOREGROUND\" value=\"")?;
+                foreground.split_once('"').map(|(color, _)| color)
+            };
+
+            assert!(program.contains(&format!(
+                r#"<scheme name="highlow-{background}" version="142" parent_scheme="{parent}">"#
+            )));
+            assert!(program.ends_with("</scheme>"));
+            let opened = program
+                .lines()
+                .filter(|line| line.trim().starts_with("<option") && line.ends_with("\">"));
+            assert_eq!(opened.count(), program.matches("</option>").count());
+            assert!(program.contains(&format!(
+                r#"<option name="CONSOLE_BACKGROUND_KEY" value="{}" />"#,
+                color("ui.bg")
+            )));
+            // `@label` falls back to `Label`, which links to `Statement`
+            assert_eq!(foreground("DEFAULT_LABEL"), Some(&*color("syn.statement")));
+            // the overrides
+            assert_eq!(foreground("DEFAULT_KEYWORD"), Some(&*color("syn.comment")));
+            assert!(!program.contains("\"CARET_ROW_COLOR\""));
+        }
+    }
+
+    #[test]
+    fn escaped_keys() {
+        let config = format!(
+            "{}\n[scopes.jetbrains]\n'A<B&\"C' = \"Keyword\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let program = compile(&colorscheme, Kind::Dark).unwrap();
+
+        assert!(program.contains(r#"<option name="A&lt;B&amp;&quot;C">"#));
+        assert!(!program.contains("A<B"));
+    }
+}
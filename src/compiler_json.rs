@@ -0,0 +1,227 @@
+//! JSON export of the resolved colors and highlights.
+//!
+//! The output is meant to be consumed by other programs, so its schema is kept stable. Fields
+//! are only ever added to it; a change that breaks existing consumers bumps `version`. The
+//! schema is documented in the README.
+
+use std::collections::BTreeMap;
+
+use color::{Oklch, OpaqueColor};
+use serde::Serialize;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Rgb8, Theme};
+use crate::configuration::{Highlight, Kind, NamespacedThemeElement};
+use crate::error::Error;
+
+/// The version of the schema.
+const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    name: &'a str,
+    kind: Kind,
+    backgrounds: Backgrounds<'a>,
+}
+
+#[derive(Serialize)]
+struct Backgrounds<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    light: Option<Background<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dark: Option<Background<'a>>,
+}
+
+#[derive(Serialize)]
+struct Background<'a> {
+    elements: BTreeMap<String, Element>,
+    highlights: BTreeMap<&'a str, ResolvedHighlight<'a>>,
+}
+
+#[derive(Serialize)]
+struct Element {
+    hex: Rgb8,
+    /// The color as computed from the configuration, before gamut mapping.
+    oklch: [f32; 3],
+    /// The color after gamut mapping and quantization, i.e., of `hex`.
+    oklch_mapped: [f32; 3],
+}
+
+#[derive(Serialize)]
+struct ResolvedHighlight<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fg: Option<Attribute<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bg: Option<Attribute<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sp: Option<Attribute<'a>>,
+    styles: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Attribute<'a> {
+    element: NamespacedThemeElement<'a>,
+    hex: Rgb8,
+}
+
+/// The components of an Oklch color, with the hue normalized to `[0, 360)`. A negative chroma
+/// (e.g., of a color desaturated past gray) is made positive by taking the opposite hue. The hue
+/// of an achromatic color is meaningless and set to 0.
+pub(crate) fn components(color: OpaqueColor<Oklch>) -> [f32; 3] {
+    let [l, c, h] = color.components;
+    let (c, h) = match c < 0. {
+        true => (-c, h + 180.),
+        false => (c, h),
+    };
+    let h = if h.is_finite() && c >= 1e-4 {
+        h.rem_euclid(360.)
+    } else {
+        0.
+    };
+    [l, c, h]
+}
+
+fn attribute<'a>(
+    theme: &Theme,
+    element: Option<NamespacedThemeElement<'a>>,
+) -> Result<Option<Attribute<'a>>, Error> {
+    let Some(element) = element else {
+        return Ok(None);
+    };
+    let color = theme
+        .get_color(element)
+        .ok_or_else(|| Error::ThemeElementMissing(format!("{element}")))?;
+    Ok(Some(Attribute {
+        element,
+        hex: color,
+    }))
+}
+
+fn styles(highlight: &Highlight) -> Vec<&'static str> {
+    [
+        ("bold", highlight.bold),
+        ("italic", highlight.italic),
+        ("underline", highlight.underline),
+        ("undercurl", highlight.undercurl),
+        ("underdouble", highlight.underdouble),
+        ("underdotted", highlight.underdotted),
+        ("underdashed", highlight.underdashed),
+        ("strikethrough", highlight.strikethrough),
+        ("reverse", highlight.reverse),
+        ("nocombine", highlight.nocombine),
+    ]
+    .into_iter()
+    .filter_map(|(style, set)| set.unwrap_or(false).then_some(style))
+    .collect()
+}
+
+fn compile_background<'a>(
+    colorscheme: &Colorscheme<'a>,
+    background: Kind,
+) -> Result<Background<'a>, Error> {
+    let theme = colorscheme.theme(background);
+
+    let mut elements = BTreeMap::new();
+    for (element, color) in theme.elements() {
+        let oklch = theme
+            .get_oklch(element)
+            .ok_or_else(|| Error::ThemeElementMissing(format!("{element}")))?;
+        elements.insert(
+            format!("{element}"),
+            Element {
+                hex: color,
+                oklch: components(oklch),
+                oklch_mapped: components(color.to_srgb().convert::<Oklch>()),
+            },
+        );
+    }
+
+    let mut highlights = BTreeMap::new();
+    for (group, highlight) in colorscheme.highlights() {
+        // links to groups the color scheme does not define fall back as they do in Neovim
+        let resolved = match highlight.link {
+            Some(link) => captures::resolve(colorscheme, link).unwrap_or(Highlight::empty()),
+            None => highlight,
+        };
+        highlights.insert(
+            group,
+            ResolvedHighlight {
+                link: highlight.link,
+                fg: attribute(theme, resolved.fg)?,
+                bg: attribute(theme, resolved.bg)?,
+                sp: attribute(theme, resolved.sp)?,
+                styles: styles(&resolved),
+            },
+        );
+    }
+
+    Ok(Background {
+        elements,
+        highlights,
+    })
+}
+
+/// Compile a color scheme to a JSON document holding, for each of the given backgrounds, the
+/// colors of all theme elements and all highlight groups with their links resolved.
+///
+/// Theme element colors are given in hexadecimal notation and in Oklch, both before and after
+/// gamut mapping. Highlight groups give the theme element and color of their attributes.
+pub fn compile(colorscheme: &Colorscheme, backgrounds: &[Kind]) -> Result<String, Error> {
+    let background = |kind| {
+        backgrounds
+            .contains(&kind)
+            .then(|| compile_background(colorscheme, kind))
+            .transpose()
+    };
+    let document = Document {
+        version: VERSION,
+        name: colorscheme.name(),
+        kind: colorscheme.kind(),
+        backgrounds: Backgrounds {
+            light: background(Kind::Light)?,
+            dark: background(Kind::Dark)?,
+        },
+    };
+
+    serde_json::to_string_pretty(&document).map_err(|_| Error::CompilationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{compile, VERSION};
+    use crate::configuration::Kind;
+
+    #[test]
+    fn schema() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let json = compile(&colorscheme, &[Kind::Light, Kind::Dark]).unwrap();
+        let document: Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(document["version"], VERSION);
+        for kind in ["light", "dark"] {
+            let elements = document["backgrounds"][kind]["elements"]
+                .as_object()
+                .unwrap();
+            assert!(elements.contains_key("diagnostics.error"));
+            for element in elements.values() {
+                assert!(element["hex"].is_string());
+                for key in ["oklch", "oklch_mapped"] {
+                    assert_eq!(element[key].as_array().map(Vec::len), Some(3));
+                }
+            }
+        }
+
+        // the example in the README
+        let light = &document["backgrounds"]["light"];
+        assert_eq!(light["elements"]["diagnostics.error"]["hex"], "#67222c");
+        let highlight = &light["highlights"]["@lsp.type.string"];
+        assert_eq!(highlight["link"], "@string");
+        assert_eq!(highlight["fg"]["element"], "syn.string");
+        assert_eq!(highlight["fg"]["hex"], "#2c451e");
+    }
+}
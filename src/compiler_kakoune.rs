@@ -0,0 +1,171 @@
+//! Kakoune color scheme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::compiler_common::finish;
+use crate::configuration::Kind;
+use crate::error::Error;
+
+/// The default mapping of Kakoune faces onto highlight groups and tree-sitter captures. This can
+/// be overridden in the configuration's `[scopes.kakoune]` section.
+const FACES: &[(&str, &str)] = &[
+    // UI
+    ("BufferPadding", "EndOfBuffer"),
+    ("Default", "Normal"),
+    ("DiagnosticError", "DiagnosticError"),
+    ("DiagnosticWarning", "DiagnosticWarn"),
+    ("Error", "ErrorMsg"),
+    ("Information", "NormalFloat"),
+    ("InlineInformation", "NormalFloat"),
+    ("LineNumberCursor", "CursorLineNr"),
+    ("LineNumbers", "LineNr"),
+    ("LineNumbersWrapped", "NonText"),
+    ("MatchingChar", "MatchParen"),
+    ("MenuBackground", "Pmenu"),
+    ("MenuForeground", "PmenuSel"),
+    ("PrimaryCursor", "Cursor"),
+    ("PrimaryCursorEol", "Cursor"),
+    ("PrimarySelection", "Visual"),
+    ("Prompt", "Question"),
+    ("SecondaryCursor", "Cursor"),
+    ("SecondaryCursorEol", "Cursor"),
+    ("SecondarySelection", "VisualNOS"),
+    ("StatusCursor", "Cursor"),
+    ("StatusLine", "StatusLine"),
+    ("StatusLineInfo", "MoreMsg"),
+    ("StatusLineMode", "ModeMsg"),
+    ("Whitespace", "Whitespace"),
+    ("WrapMarker", "NonText"),
+    // Code
+    ("attribute", "@attribute"),
+    ("builtin", "@function.builtin"),
+    ("comment", "@comment"),
+    ("documentation", "@comment.documentation"),
+    ("function", "@function"),
+    ("keyword", "@keyword"),
+    ("meta", "@keyword.directive"),
+    ("module", "@module"),
+    ("operator", "@operator"),
+    ("string", "@string"),
+    ("type", "@type"),
+    ("value", "@constant"),
+    ("variable", "@variable"),
+    // Markup
+    ("block", "@markup.raw.block"),
+    ("bullet", "@markup.list"),
+    ("header", "@markup.heading"),
+    ("link", "@markup.link"),
+    ("list", "@markup.list"),
+    ("mono", "@markup.raw"),
+    ("title", "@markup.heading"),
+];
+
+/// A color in Kakoune's notation, or `default` if not set.
+fn color(color: Option<Rgb8>) -> String {
+    match color {
+        Some(Rgb8 { red, green, blue }) => format!("rgb:{red:02x}{green:02x}{blue:02x}"),
+        None => "default".to_owned(),
+    }
+}
+
+fn compile_program(colorscheme: &Colorscheme, background: Kind) -> Result<String> {
+    let theme = colorscheme.theme(background);
+    let mut program = String::with_capacity(4_096);
+
+    writeln!(program, "# {}-{background}", colorscheme.name())?;
+    writeln!(program, "# Generated by hi.nvim.rs.")?;
+    writeln!(program)?;
+
+    for (face, group) in captures::scope_mapping(colorscheme, "kakoune", FACES) {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+
+        let fg = highlight.fg.and_then(|fg| theme.get_color(fg));
+        let bg = highlight.bg.and_then(|bg| theme.get_color(bg));
+        let sp = highlight.sp.and_then(|sp| theme.get_color(sp));
+
+        let mut attributes = String::new();
+        for (set, attribute) in [
+            (highlight.bold, 'b'),
+            (highlight.italic, 'i'),
+            (highlight.underline, 'u'),
+            (highlight.underdotted, 'u'),
+            (highlight.underdashed, 'u'),
+            (highlight.undercurl, 'c'),
+            (highlight.underdouble, 'U'),
+            (highlight.strikethrough, 's'),
+            (highlight.reverse, 'r'),
+        ] {
+            if set.unwrap_or(false) && !attributes.contains(attribute) {
+                attributes.push(attribute);
+            }
+        }
+
+        write!(program, "face global {face} {},{}", color(fg), color(bg))?;
+        if sp.is_some() {
+            write!(program, ",{}", color(sp))?;
+        }
+        if !attributes.is_empty() {
+            write!(program, "+{attributes}")?;
+        }
+        writeln!(program)?;
+    }
+
+    Ok(program)
+}
+
+/// Compile the light or dark theme of a color scheme to a Kakoune color scheme, to be placed in
+/// `colors/<name>-<background>.kak`.
+///
+/// Highlights are mapped onto Kakoune's faces, following links to their final groups. The
+/// mapping can be overridden in the configuration's `[scopes.kakoune]` section.
+pub fn compile(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    finish(compile_program(colorscheme, background))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn faces() {
+        let config = format!(
+            "{}\n[scopes.kakoune]\n\"keyword\" = \"Comment\"\n\"module\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        for background in [Kind::Light, Kind::Dark] {
+            let program = compile(&colorscheme, background).unwrap();
+            let face = |name: &str| {
+                program
+                    .lines()
+                    .find_map(|line| line.strip_prefix(&format!("face global {name} ")))
+            };
+            // Kakoune's colors are written as `rgb:rrggbb`
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                color.unwrap().to_string().replace('#', "rgb:")
+            };
+
+            assert!(program.starts_with(&format!("# highlow-{background}")));
+            for line in program.lines().filter(|line| !line.starts_with('#')) {
+                assert!(line.is_empty() || line.starts_with("face global "));
+            }
+            assert_eq!(
+                face("Default"),
+                Some(&*format!("{},{}", color("ui.fg"), color("ui.bg")))
+            );
+            // `@keyword.directive` falls back to `PreProc`
+            assert!(face("meta").is_some_and(|face| face.starts_with(&color("syn.preproc"))));
+            // the overrides
+            assert!(face("keyword").is_some_and(|face| face.starts_with(&color("syn.comment"))));
+            assert!(face("module").is_none());
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Micro color scheme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::Colorscheme;
+use crate::compiler_common::finish;
+use crate::configuration::Kind;
+use crate::error::Error;
+
+/// The default mapping of Micro's color groups onto highlight groups and tree-sitter captures.
+/// This can be overridden in the configuration's `[scopes.micro]` section.
+const GROUPS: &[(&str, &str)] = &[
+    // UI
+    ("color-column", "ColorColumn"),
+    ("current-line-number", "CursorLineNr"),
+    ("cursor-line", "CursorLine"),
+    ("default", "Normal"),
+    ("diff-added", "diffAdded"),
+    ("diff-deleted", "diffRemoved"),
+    ("diff-modified", "diffChanged"),
+    ("divider", "WinSeparator"),
+    ("error-message", "ErrorMsg"),
+    ("gutter-error", "DiagnosticSignError"),
+    ("gutter-warning", "DiagnosticSignWarn"),
+    ("hlsearch", "Search"),
+    ("indent-char", "Whitespace"),
+    ("line-number", "LineNr"),
+    ("match-brace", "MatchParen"),
+    ("selection", "Visual"),
+    ("statusline", "StatusLine"),
+    ("statusline.inactive", "StatusLineNC"),
+    ("tabbar", "StatusLineNC"),
+    // Syntax
+    ("comment", "Comment"),
+    ("constant", "Constant"),
+    ("constant.bool", "Boolean"),
+    ("constant.number", "Number"),
+    ("constant.specialChar", "SpecialChar"),
+    ("constant.string", "String"),
+    ("error", "Error"),
+    ("identifier", "Identifier"),
+    ("ignore", "Ignore"),
+    ("preproc", "PreProc"),
+    ("special", "Special"),
+    ("statement", "Statement"),
+    ("symbol", "Operator"),
+    ("symbol.brackets", "@punctuation.bracket"),
+    ("symbol.tag", "@tag"),
+    ("todo", "Todo"),
+    ("type", "Type"),
+    ("type.keyword", "StorageClass"),
+    ("underlined", "Underlined"),
+];
+
+/// Micro's color groups that only take a background color, which is given as their foreground.
+const BACKGROUND_GROUPS: &[&str] = &["color-column", "cursor-line"];
+
+fn compile_program(colorscheme: &Colorscheme, background: Kind) -> Result<String> {
+    let theme = colorscheme.theme(background);
+    let mut program = String::with_capacity(4_096);
+
+    writeln!(program, "# {}-{background}", colorscheme.name())?;
+    writeln!(program, "# Generated by hi.nvim.rs.")?;
+    writeln!(program)?;
+
+    for (group, highlight_group) in captures::scope_mapping(colorscheme, "micro", GROUPS) {
+        let Some(highlight) = captures::resolve(colorscheme, highlight_group) else {
+            continue;
+        };
+
+        let fg = highlight.fg.and_then(|fg| theme.get_color(fg));
+        let bg = highlight.bg.and_then(|bg| theme.get_color(bg));
+
+        if BACKGROUND_GROUPS.contains(&group) {
+            if let Some(bg) = bg {
+                writeln!(program, r#"color-link {group} "{bg}""#)?;
+            }
+            continue;
+        }
+
+        let mut style = Vec::new();
+        for (set, attribute) in [
+            (highlight.bold, "bold"),
+            (highlight.italic, "italic"),
+            (highlight.underline, "underline"),
+            (highlight.undercurl, "underline"),
+            (highlight.underdouble, "underline"),
+            (highlight.underdotted, "underline"),
+            (highlight.underdashed, "underline"),
+            (highlight.reverse, "reverse"),
+        ] {
+            if set.unwrap_or(false) && !style.contains(&attribute) {
+                style.push(attribute);
+            }
+        }
+        let colors = match (fg, bg) {
+            (Some(fg), Some(bg)) => format!("{fg},{bg}"),
+            (Some(fg), None) => fg.to_string(),
+            (None, Some(bg)) => format!("default,{bg}"),
+            (None, None) => "default".to_owned(),
+        };
+        style.push(&colors);
+
+        writeln!(program, r#"color-link {group} "{}""#, style.join(" "))?;
+    }
+
+    Ok(program)
+}
+
+/// Compile the light or dark theme of a color scheme to a Micro color scheme, to be placed in
+/// `colorschemes/<name>-<background>.micro`.
+///
+/// Highlights are mapped onto Micro's color groups, following links to their final groups. The
+/// mapping can be overridden in the configuration's `[scopes.micro]` section.
+pub fn compile(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    finish(compile_program(colorscheme, background))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn color_groups() {
+        let config = format!(
+            "{}\n[scopes.micro]\n\"statement\" = \"Comment\"\n\"todo\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        for background in [Kind::Light, Kind::Dark] {
+            let program = compile(&colorscheme, background).unwrap();
+            let group = |name: &str| {
+                program
+                    .lines()
+                    .find_map(|line| line.strip_prefix(&format!("color-link {name} ")))
+            };
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                color.unwrap().to_string()
+            };
+
+            assert!(program.starts_with(&format!("# highlow-{background}")));
+            for line in program.lines().filter(|line| !line.starts_with('#')) {
+                assert!(line.is_empty() || line.starts_with("color-link "));
+            }
+            assert_eq!(
+                group("default"),
+                Some(&*format!("\"{},{}\"", color("ui.fg"), color("ui.bg")))
+            );
+            // `StorageClass` links to `Type`
+            assert!(group("type.keyword").is_some_and(|group| group.contains(&color("syn.type"))));
+            // the overrides
+            assert!(group("statement").is_some_and(|group| group.contains(&color("syn.comment"))));
+            assert!(group("todo").is_none());
+        }
+    }
+}
@@ -3,11 +3,13 @@ use std::fmt::Write;
 use anyhow::Result;
 
 use crate::colorscheme::{Colorscheme, Theme};
-use crate::configuration::Highlight;
+use crate::configuration::{Highlight, Kind};
 use crate::error::Error;
+use crate::options::CompileOptions;
 
 struct Compiler<'c> {
     colorscheme: &'c Colorscheme<'c>,
+    options: &'c CompileOptions<'c>,
     program: String,
     indent: u8,
 }
@@ -31,9 +33,14 @@ impl Compiler<'_> {
             writeln!(self.program, "local {namespace} = {{",)?;
 
             self.indent();
-            for (name, theme_color) in theme_colors {
+            for (name, &theme_color) in theme_colors {
                 self.write_indent();
-                write!(self.program, r#"["{name}"] = "{theme_color}""#)?;
+                if let Some(palette) = self.options.terminal16 {
+                    let slot = palette.closest_slot(theme_color);
+                    write!(self.program, r#"["{name}"] = {slot}"#)?;
+                } else {
+                    write!(self.program, r#"["{name}"] = "{theme_color}""#)?;
+                }
                 writeln!(self.program, ",")?;
             }
             self.dedent();
@@ -61,13 +68,20 @@ impl Compiler<'_> {
             }
         }
 
+        // In `notermguicolors` color schemes, the colors are terminal color slots.
+        let (fg_key, bg_key) = if self.options.terminal16.is_some() {
+            ("ctermfg", "ctermbg")
+        } else {
+            ("fg", "bg")
+        };
+
         if highlight.link.is_some() {
-            write_quoted(&mut self.program, "link", highlight.link.as_deref());
+            write_quoted(&mut self.program, "link", highlight.link);
         } else {
             if let Some(fg) = highlight.fg {
                 write!(
                     self.program,
-                    r#"fg = {ns}["{name}"], "#,
+                    r#"{fg_key} = {ns}["{name}"], "#,
                     ns = fg.theme_namespace,
                     name = fg.element_name,
                 )?;
@@ -75,12 +89,13 @@ impl Compiler<'_> {
             if let Some(bg) = highlight.bg {
                 write!(
                     self.program,
-                    "bg = {ns}.{name}, ",
+                    "{bg_key} = {ns}.{name}, ",
                     ns = bg.theme_namespace,
                     name = bg.element_name,
                 )?;
             }
-            if let Some(sp) = highlight.sp {
+            // Neovim has no terminal equivalent of the special color.
+            if let Some(sp) = highlight.sp.filter(|_| self.options.terminal16.is_none()) {
                 write!(
                     self.program,
                     "sp = {ns}.{name}, ",
@@ -89,8 +104,19 @@ impl Compiler<'_> {
                 )?;
             }
 
-            for style in highlight.gui_styles_iter() {
-                write_unquoted(&mut self.program, style, Some("true"));
+            if self.options.terminal16.is_some() {
+                let mut styles = highlight.gui_styles_iter().peekable();
+                if styles.peek().is_some() {
+                    write!(self.program, "cterm = {{ ")?;
+                    for style in styles {
+                        write_unquoted(&mut self.program, style, Some("true"));
+                    }
+                    write!(self.program, "}}, ")?;
+                }
+            } else {
+                for style in highlight.gui_styles_iter() {
+                    write_unquoted(&mut self.program, style, Some("true"));
+                }
             }
         }
 
@@ -105,7 +131,7 @@ impl Compiler<'_> {
 
         self.compile_themes(theme)?;
 
-        writeln!(self.program, "")?;
+        writeln!(self.program)?;
         self.write_indent();
         writeln!(self.program, "-- Highlights")?;
 
@@ -113,6 +139,35 @@ impl Compiler<'_> {
             self.compile_highlight_group(name, highlight)?;
         }
 
+        self.compile_terminal_colors()?;
+
+        Ok(())
+    }
+
+    /// Set the colors of `:terminal` buffers, if the color scheme declares terminal colors.
+    fn compile_terminal_colors(&mut self) -> Result<()> {
+        // `notermguicolors` terminals use the terminal's own palette.
+        if self.options.terminal16.is_some() {
+            return Ok(());
+        }
+        let Some(elements) = crate::terminal::ansi_elements(self.colorscheme)? else {
+            return Ok(());
+        };
+
+        writeln!(self.program)?;
+        self.write_indent();
+        writeln!(self.program, "-- Terminal colors")?;
+
+        for (slot, element) in elements.iter().enumerate() {
+            self.write_indent();
+            writeln!(
+                self.program,
+                r#"vim.g.terminal_color_{slot} = {ns}["{name}"]"#,
+                ns = element.theme_namespace,
+                name = element.element_name,
+            )?;
+        }
+
         Ok(())
     }
 
@@ -139,51 +194,178 @@ impl Compiler<'_> {
 
         Ok(())
     }
+
+    /// Write the light and dark setup functions, and call one of them depending on Neovim's
+    /// `background` option.
+    ///
+    /// If only one of the themes is compiled, its setup function is called unconditionally.
+    fn compile_setup(&mut self) -> Result<()> {
+        let lines: &[&str] = match self.options.background {
+            None => {
+                self.compile_light_colorscheme()?;
+                writeln!(self.program)?;
+                self.compile_dark_colorscheme()?;
+                &[
+                    r#"if vim.o.background == "dark" then"#,
+                    "    setupDarkColorscheme()",
+                    "else",
+                    "    setupLightColorscheme()",
+                    "end",
+                ]
+            }
+            Some(Kind::Light) => {
+                self.compile_light_colorscheme()?;
+                &["setupLightColorscheme()"]
+            }
+            Some(Kind::Dark) => {
+                self.compile_dark_colorscheme()?;
+                &["setupDarkColorscheme()"]
+            }
+        };
+        writeln!(self.program)?;
+
+        for line in lines {
+            self.write_indent();
+            writeln!(self.program, "{line}")?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Compile a color scheme to a Neovim configuration.
 // This should probably be infallible.
 pub fn compile(colorscheme: &Colorscheme) -> std::result::Result<String, Error> {
+    compile_with_options(colorscheme, &CompileOptions::default())
+}
+
+/// Compile a color scheme to a Neovim configuration, with compilation options.
+pub fn compile_with_options(
+    colorscheme: &Colorscheme,
+    options: &CompileOptions,
+) -> std::result::Result<String, Error> {
     let mut compiler = Compiler {
         colorscheme,
+        options,
         program: String::with_capacity(8192),
         indent: 0,
     };
 
+    // Setting the background reloads the current color scheme, so this must happen first.
+    if let Some(background) = options.forced_background() {
+        writeln!(compiler.program, "set background={background}")
+            .map_err(|_| Error::CompilationFailed)?;
+    }
+
     write!(
         compiler.program,
         r#"hi clear
-set termguicolors
+set {termguicolors}
 let g:colors_name = "{name}"
 
 lua << EOF
 "#,
-        name = colorscheme.name
+        termguicolors = if options.terminal16.is_some() {
+            "notermguicolors"
+        } else {
+            "termguicolors"
+        },
+        name = options.colors_name(colorscheme),
     )
     .map_err(|_| Error::CompilationFailed)?;
 
     compiler.indent();
     compiler
-        .compile_light_colorscheme()
-        .map_err(|_| Error::CompilationFailed)?;
-    writeln!(compiler.program).map_err(|_| Error::CompilationFailed)?;
-    compiler
-        .compile_dark_colorscheme()
+        .compile_setup()
         .map_err(|_| Error::CompilationFailed)?;
     compiler.dedent();
 
-    writeln!(compiler.program).map_err(|_| Error::CompilationFailed)?;
+    writeln!(compiler.program, "EOF").map_err(|_| Error::CompilationFailed)?;
+
+    Ok(compiler.program)
+}
+
+/// Compile a color scheme to a standalone Neovim Lua color scheme (to be placed in
+/// `colors/<name>.lua`).
+pub fn compile_lua(colorscheme: &Colorscheme) -> std::result::Result<String, Error> {
+    compile_lua_with_options(colorscheme, &CompileOptions::default())
+}
+
+/// Compile a color scheme to a standalone Neovim Lua color scheme, with compilation options.
+pub fn compile_lua_with_options(
+    colorscheme: &Colorscheme,
+    options: &CompileOptions,
+) -> std::result::Result<String, Error> {
+    let mut compiler = Compiler {
+        colorscheme,
+        options,
+        program: String::with_capacity(8192),
+        indent: 0,
+    };
+
+    // Setting the background reloads the current color scheme, so this must happen first.
+    if let Some(background) = options.forced_background() {
+        writeln!(compiler.program, r#"vim.o.background = "{background}""#)
+            .map_err(|_| Error::CompilationFailed)?;
+    }
 
-    writeln!(
+    write!(
         compiler.program,
-        r#"    if vim.o.background == "dark" then
-        setupDarkColorscheme()
-    else
-        setupLightColorscheme()
-    end
-EOF"#
+        r#"vim.cmd.highlight("clear")
+vim.o.termguicolors = {termguicolors}
+vim.g.colors_name = "{name}"
+
+"#,
+        termguicolors = options.terminal16.is_none(),
+        name = options.colors_name(colorscheme),
     )
     .map_err(|_| Error::CompilationFailed)?;
 
-    Ok(compiler.program)
+    compiler
+        .compile_setup()
+        .map_err(|_| Error::CompilationFailed)?;
+
+    Ok(compiler.program.trim_end().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+
+    /// highlow with its terminal colors declared: red as `diagnostics.error`, black as `ui.bg`
+    /// and the others as `ui.fg`.
+    fn terminal_config() -> String {
+        let mut config = format!(
+            "{}\n[terminal]\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        for name in crate::terminal16::SLOT_NAMES {
+            let element = match name {
+                "black" => "ui.bg",
+                "red" => "diagnostics.error",
+                _ => "ui.fg",
+            };
+            config += &format!("{name} = \"{element}\"\n");
+        }
+        config
+    }
+
+    #[test]
+    fn terminal_colors() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        assert!(!compile(&colorscheme).unwrap().contains("terminal_color"));
+
+        let config = terminal_config();
+        let colorscheme = crate::parse(&config).unwrap();
+        let program = compile(&colorscheme).unwrap();
+        // set in both the light and the dark setup function
+        assert_eq!(program.matches("vim.g.terminal_color_").count(), 32);
+        assert_eq!(
+            program
+                .matches(r#"vim.g.terminal_color_1 = diagnostics["error"]"#)
+                .count(),
+            2
+        );
+        assert!(program.contains(r#"vim.g.terminal_color_15 = ui["fg"]"#));
+    }
 }
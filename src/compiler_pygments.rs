@@ -0,0 +1,309 @@
+//! Pygments style compilation.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Rgb8, Theme};
+use crate::compiler_common::finish;
+use crate::configuration::{Highlight, Kind};
+use crate::error::Error;
+
+/// The default mapping of Pygments' token types and style attributes onto highlight groups and
+/// tree-sitter captures. This can be overridden in the configuration's `[scopes.pygments]`
+/// section.
+const TOKENS: &[(&str, &str)] = &[
+    // style attributes, taking the background (or foreground) color of their group
+    ("background_color", "Normal"),
+    ("highlight_color", "Visual"),
+    ("line_number_background_color", "LineNr"),
+    ("line_number_color", "LineNr"),
+    ("line_number_special_background_color", "CursorLineNr"),
+    ("line_number_special_color", "CursorLineNr"),
+    // token types
+    ("Token", "Normal"),
+    ("Comment", "Comment"),
+    ("Comment.Preproc", "PreProc"),
+    ("Comment.PreprocFile", "@string.special.path"),
+    ("Comment.Special", "SpecialComment"),
+    ("Error", "Error"),
+    ("Generic.Deleted", "diffRemoved"),
+    ("Generic.Emph", "@markup.italic"),
+    ("Generic.Error", "ErrorMsg"),
+    ("Generic.Heading", "@markup.heading"),
+    ("Generic.Inserted", "diffAdded"),
+    ("Generic.Strong", "@markup.strong"),
+    ("Generic.Subheading", "@markup.heading"),
+    ("Generic.Traceback", "ErrorMsg"),
+    ("Keyword", "Keyword"),
+    ("Keyword.Constant", "@constant.builtin"),
+    ("Keyword.Namespace", "@keyword.import"),
+    ("Keyword.Type", "@type.builtin"),
+    ("Literal.Number", "Number"),
+    ("Literal.String", "String"),
+    ("Literal.String.Char", "Character"),
+    ("Literal.String.Doc", "@string.documentation"),
+    ("Literal.String.Escape", "@string.escape"),
+    ("Literal.String.Interpol", "Special"),
+    ("Literal.String.Regex", "@string.regexp"),
+    ("Literal.String.Symbol", "@string.special.symbol"),
+    ("Name", "@variable"),
+    ("Name.Attribute", "@tag.attribute"),
+    ("Name.Builtin", "@function.builtin"),
+    ("Name.Builtin.Pseudo", "@variable.builtin"),
+    ("Name.Class", "@type"),
+    ("Name.Constant", "Constant"),
+    ("Name.Decorator", "@attribute"),
+    ("Name.Exception", "@type"),
+    ("Name.Function", "Function"),
+    ("Name.Label", "@label"),
+    ("Name.Namespace", "@module"),
+    ("Name.Property", "@property"),
+    ("Name.Tag", "@tag"),
+    ("Name.Variable.Magic", "@variable.builtin"),
+    ("Operator", "Operator"),
+    ("Operator.Word", "@keyword.operator"),
+    ("Punctuation", "Delimiter"),
+];
+
+/// The name of the Python class of a theme (e.g., `HighlowDarkStyle` for `highlow`).
+fn class_name(name: &str, background: Kind) -> String {
+    let mut class = String::with_capacity(name.len() + 10);
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            class.push(first.to_ascii_uppercase());
+            class.extend(chars);
+        }
+    }
+    if !class.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        class.insert_str(0, "Hl");
+    }
+    match background {
+        Kind::Light => class.push_str("LightStyle"),
+        Kind::Dark => class.push_str("DarkStyle"),
+    }
+    class
+}
+
+/// The resolved style of a token type.
+#[derive(Default)]
+struct TokenStyle {
+    fg: Option<Rgb8>,
+    bg: Option<Rgb8>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl TokenStyle {
+    fn new(theme: &Theme, highlight: Highlight) -> Self {
+        let (fg, bg) = match highlight.reverse.unwrap_or(false) {
+            true => (highlight.bg, highlight.fg),
+            false => (highlight.fg, highlight.bg),
+        };
+        TokenStyle {
+            fg: fg.and_then(|fg| theme.get_color(fg)),
+            bg: bg.and_then(|bg| theme.get_color(bg)),
+            bold: highlight.bold.unwrap_or(false),
+            italic: highlight.italic.unwrap_or(false),
+            underline: [
+                highlight.underline,
+                highlight.undercurl,
+                highlight.underdouble,
+                highlight.underdotted,
+                highlight.underdashed,
+            ]
+            .into_iter()
+            .any(|set| set.unwrap_or(false)),
+        }
+    }
+}
+
+/// The token type a token type inherits its style from, if any (e.g., `Literal` for
+/// `Literal.String`, and `Token` for `Literal`).
+fn parent(token: &str) -> Option<&str> {
+    match token.rsplit_once('.') {
+        Some((parent, _)) => Some(parent),
+        None if token != "Token" => Some("Token"),
+        None => None,
+    }
+}
+
+fn compile_class(
+    program: &mut String,
+    colorscheme: &Colorscheme,
+    mapping: &BTreeMap<&str, &str>,
+    background: Kind,
+) -> Result<()> {
+    let theme = colorscheme.theme(background);
+
+    let mut attributes = Vec::new();
+    let mut styles = BTreeMap::new();
+    for (&token, &group) in mapping {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+        let mut style = TokenStyle::new(theme, highlight);
+        if token.ends_with("_color") {
+            let color = match token.ends_with("background_color") || token == "highlight_color" {
+                true => style.bg,
+                false => style.fg,
+            };
+            if let Some(color) = color {
+                attributes.push((token, color));
+            }
+        } else {
+            // the root token takes the foreground only, the background is `background_color`
+            if token == "Token" {
+                style.bg = None;
+            }
+            styles.insert(token, style);
+        }
+    }
+
+    writeln!(program)?;
+    writeln!(program)?;
+    writeln!(
+        program,
+        "class {}(Style):",
+        class_name(colorscheme.name(), background)
+    )?;
+    writeln!(
+        program,
+        "    name = {:?}",
+        format!("{}-{background}", colorscheme.name())
+    )?;
+    writeln!(program)?;
+    for (attribute, color) in attributes {
+        writeln!(program, "    {attribute} = \"{color}\"")?;
+    }
+    writeln!(program)?;
+    writeln!(program, "    styles = {{")?;
+
+    let normal = styles.get("Token").and_then(|style| style.fg);
+    let mut tokens: Vec<_> = styles.iter().collect();
+    tokens.sort_by_key(|&(&token, _)| (token != "Token", token));
+    for (&token, style) in tokens {
+        // Token types inherit the style of their parent, whereas highlight groups do not inherit.
+        // Whatever the closest styled parent sets that this token type does not is reset.
+        let inherited = std::iter::successors(parent(token), |&token| parent(token))
+            .find_map(|token| styles.get(token));
+        let default = TokenStyle::default();
+        let inherited = inherited.unwrap_or(&default);
+
+        let mut definition = Vec::new();
+        for (set, inherited, style) in [
+            (style.bold, inherited.bold, "bold"),
+            (style.italic, inherited.italic, "italic"),
+            (style.underline, inherited.underline, "underline"),
+        ] {
+            match (set, inherited) {
+                (true, _) => definition.push(style.to_owned()),
+                (false, true) => definition.push(format!("no{style}")),
+                (false, false) => {}
+            }
+        }
+        match style.fg {
+            Some(fg) => definition.push(format!("{fg}")),
+            None if inherited.fg.is_some() && inherited.fg != normal => {
+                definition.extend(normal.map(|fg| format!("{fg}")));
+            }
+            None => {}
+        }
+        match style.bg {
+            Some(bg) => definition.push(format!("bg:{bg}")),
+            None if inherited.bg.is_some() => definition.push("bg:".to_owned()),
+            None => {}
+        }
+
+        if definition.is_empty() {
+            continue;
+        }
+        let token = match token {
+            "Token" => "Token".to_owned(),
+            token => format!("Token.{token}"),
+        };
+        writeln!(program, "        {token}: \"{}\",", definition.join(" "))?;
+    }
+    writeln!(program, "    }}")?;
+
+    Ok(())
+}
+
+fn compile_program(colorscheme: &Colorscheme, backgrounds: &[Kind]) -> Result<String> {
+    let mut program = String::with_capacity(8_192);
+    let mapping = captures::scope_mapping(colorscheme, "pygments", TOKENS);
+
+    writeln!(
+        program,
+        "\"\"\"The {} Pygments style. Generated by hi.nvim.rs.\"\"\"",
+        colorscheme.name()
+    )?;
+    writeln!(program)?;
+    writeln!(program, "from pygments.style import Style")?;
+    writeln!(program, "from pygments.token import Token")?;
+
+    for &background in backgrounds {
+        compile_class(&mut program, colorscheme, &mapping, background)?;
+    }
+
+    Ok(program)
+}
+
+/// Compile a color scheme to a Python module holding a Pygments style class for each of the
+/// given backgrounds, named after the color scheme (e.g., `HighlowLightStyle` and
+/// `HighlowDarkStyle`).
+///
+/// Highlights are mapped onto Pygments' token types, following links to their final groups. The
+/// style's background and highlight colors are taken from `Normal` and `Visual`. The mapping can
+/// be overridden in the configuration's `[scopes.pygments]` section.
+pub fn compile(colorscheme: &Colorscheme, backgrounds: &[Kind]) -> Result<String, Error> {
+    finish(compile_program(colorscheme, backgrounds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn styles() {
+        let config = format!(
+            "{}\n[scopes.pygments]\n\"Keyword\" = \"Comment\"\n\"Name.Tag\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let program = compile(&colorscheme, &[Kind::Light, Kind::Dark]).unwrap();
+
+        assert!(program.contains("from pygments.style import Style\n"));
+        for (background, class) in [
+            (Kind::Light, "class HighlowLightStyle(Style):"),
+            (Kind::Dark, "class HighlowDarkStyle(Style):"),
+        ] {
+            let (_, class) = program.split_once(class).unwrap();
+            let class = class.split("\nclass ").next().unwrap();
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                color.unwrap().to_string()
+            };
+            let style = |token: &str| {
+                class
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix(&format!("{token}: ")))
+            };
+
+            assert!(class.contains(&format!("name = \"highlow-{background}\"")));
+            assert!(class.contains(&format!("background_color = \"{}\"", color("ui.bg"))));
+            // `@label` falls back to `Label`, which links to `Statement`
+            let statement = format!("\"bold {}\",", color("syn.statement"));
+            assert_eq!(style("Token.Name.Label"), Some(&*statement));
+            // the overrides
+            let comment = format!("\"italic {}\",", color("syn.comment"));
+            assert_eq!(style("Token.Keyword"), Some(&*comment));
+            assert_eq!(style("Token.Name.Tag"), None);
+        }
+    }
+}
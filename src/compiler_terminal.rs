@@ -0,0 +1,392 @@
+//! Terminal emulator theme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::compiler_common::finish;
+use crate::configuration::Kind;
+use crate::error::Error;
+use crate::terminal::TerminalColors;
+use crate::terminal16::SLOT_NAMES;
+
+/// A terminal emulator to compile a theme for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emulator {
+    /// A kitty theme, to be included in `kitty.conf`.
+    Kitty,
+    /// An Alacritty theme, to be imported in `alacritty.toml`.
+    Alacritty,
+    /// A WezTerm color scheme, to be placed in WezTerm's `colors` directory.
+    Wezterm,
+    /// A foot theme, to be included in `foot.ini`.
+    Foot,
+    /// A Ghostty theme, to be placed in Ghostty's `themes` directory.
+    Ghostty,
+    /// A Windows Terminal color scheme, to be added to the `schemes` list of `settings.json`.
+    WindowsTerminal,
+    /// X resources, to be merged with `xrdb`.
+    Xresources,
+}
+
+/// A color in hexadecimal notation without the leading `#` (e.g., `3d3d3d`).
+fn bare_hex(color: Rgb8) -> String {
+    let Rgb8 { red, green, blue } = color;
+    format!("{red:02x}{green:02x}{blue:02x}")
+}
+
+/// A Windows Terminal color scheme.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowsTerminalScheme<'a> {
+    name: &'a str,
+    foreground: Rgb8,
+    background: Rgb8,
+    cursor_color: Rgb8,
+    selection_background: Rgb8,
+    black: Rgb8,
+    red: Rgb8,
+    green: Rgb8,
+    yellow: Rgb8,
+    blue: Rgb8,
+    purple: Rgb8,
+    cyan: Rgb8,
+    white: Rgb8,
+    bright_black: Rgb8,
+    bright_red: Rgb8,
+    bright_green: Rgb8,
+    bright_yellow: Rgb8,
+    bright_blue: Rgb8,
+    bright_purple: Rgb8,
+    bright_cyan: Rgb8,
+    bright_white: Rgb8,
+}
+
+struct Compiler<'c> {
+    name: &'c str,
+    colors: TerminalColors,
+    program: String,
+}
+
+impl Compiler<'_> {
+    fn compile_kitty(&mut self) -> Result<()> {
+        let TerminalColors {
+            foreground,
+            background,
+            cursor,
+            selection,
+            ..
+        } = self.colors;
+
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program)?;
+        writeln!(self.program, "foreground {foreground}")?;
+        writeln!(self.program, "background {background}")?;
+        writeln!(self.program, "cursor {cursor}")?;
+        writeln!(self.program, "cursor_text_color background")?;
+        writeln!(self.program, "selection_foreground none")?;
+        writeln!(self.program, "selection_background {selection}")?;
+        writeln!(self.program)?;
+        for (slot, color) in self.colors.ansi.iter().enumerate() {
+            writeln!(self.program, "color{slot} {color}")?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_alacritty(&mut self) -> Result<()> {
+        let TerminalColors {
+            foreground,
+            background,
+            cursor,
+            selection,
+            ..
+        } = self.colors;
+
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[colors.primary]")?;
+        writeln!(self.program, "foreground = \"{foreground}\"")?;
+        writeln!(self.program, "background = \"{background}\"")?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[colors.cursor]")?;
+        writeln!(self.program, "text = \"CellBackground\"")?;
+        writeln!(self.program, "cursor = \"{cursor}\"")?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[colors.selection]")?;
+        writeln!(self.program, "text = \"CellForeground\"")?;
+        writeln!(self.program, "background = \"{selection}\"")?;
+
+        for (table, colors) in [
+            ("normal", self.colors.normal()),
+            ("bright", self.colors.bright()),
+        ] {
+            writeln!(self.program)?;
+            writeln!(self.program, "[colors.{table}]")?;
+            for (name, color) in SLOT_NAMES.iter().zip(colors) {
+                writeln!(self.program, "{name} = \"{color}\"")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_wezterm(&mut self) -> Result<()> {
+        let TerminalColors {
+            foreground,
+            background,
+            cursor,
+            selection,
+            ..
+        } = self.colors;
+
+        let list = |colors: &[Rgb8]| {
+            colors
+                .iter()
+                .map(|color| format!("\"{color}\""))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        writeln!(self.program, "[metadata]")?;
+        writeln!(self.program, "name = \"{}\"", self.name)?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[colors]")?;
+        writeln!(self.program, "foreground = \"{foreground}\"")?;
+        writeln!(self.program, "background = \"{background}\"")?;
+        writeln!(self.program, "cursor_bg = \"{cursor}\"")?;
+        writeln!(self.program, "cursor_border = \"{cursor}\"")?;
+        writeln!(self.program, "cursor_fg = \"{background}\"")?;
+        writeln!(self.program, "selection_bg = \"{selection}\"")?;
+        writeln!(self.program, "selection_fg = \"{foreground}\"")?;
+        writeln!(self.program, "ansi = [{}]", list(self.colors.normal()))?;
+        writeln!(self.program, "brights = [{}]", list(self.colors.bright()))?;
+
+        Ok(())
+    }
+
+    fn compile_foot(&mut self) -> Result<()> {
+        let TerminalColors {
+            foreground,
+            background,
+            cursor,
+            selection,
+            ..
+        } = self.colors;
+
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[cursor]")?;
+        writeln!(
+            self.program,
+            "color={} {}",
+            bare_hex(background),
+            bare_hex(cursor)
+        )?;
+        writeln!(self.program)?;
+        writeln!(self.program, "[colors]")?;
+        writeln!(self.program, "foreground={}", bare_hex(foreground))?;
+        writeln!(self.program, "background={}", bare_hex(background))?;
+        writeln!(
+            self.program,
+            "selection-foreground={}",
+            bare_hex(foreground)
+        )?;
+        writeln!(self.program, "selection-background={}", bare_hex(selection))?;
+        for (idx, &color) in self.colors.normal().iter().enumerate() {
+            writeln!(self.program, "regular{idx}={}", bare_hex(color))?;
+        }
+        for (idx, &color) in self.colors.bright().iter().enumerate() {
+            writeln!(self.program, "bright{idx}={}", bare_hex(color))?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_ghostty(&mut self) -> Result<()> {
+        let TerminalColors {
+            foreground,
+            background,
+            cursor,
+            selection,
+            ..
+        } = self.colors;
+
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program)?;
+        writeln!(self.program, "foreground = {foreground}")?;
+        writeln!(self.program, "background = {background}")?;
+        writeln!(self.program, "cursor-color = {cursor}")?;
+        writeln!(self.program, "cursor-text = {background}")?;
+        writeln!(self.program, "selection-foreground = {foreground}")?;
+        writeln!(self.program, "selection-background = {selection}")?;
+        for (slot, color) in self.colors.ansi.iter().enumerate() {
+            writeln!(self.program, "palette = {slot}={color}")?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_windows_terminal(&mut self) -> Result<()> {
+        let TerminalColors {
+            ansi,
+            foreground,
+            background,
+            cursor,
+            selection,
+        } = self.colors;
+
+        let scheme = WindowsTerminalScheme {
+            name: self.name,
+            foreground,
+            background,
+            cursor_color: cursor,
+            selection_background: selection,
+            black: ansi[0],
+            red: ansi[1],
+            green: ansi[2],
+            yellow: ansi[3],
+            blue: ansi[4],
+            purple: ansi[5],
+            cyan: ansi[6],
+            white: ansi[7],
+            bright_black: ansi[8],
+            bright_red: ansi[9],
+            bright_green: ansi[10],
+            bright_yellow: ansi[11],
+            bright_blue: ansi[12],
+            bright_purple: ansi[13],
+            bright_cyan: ansi[14],
+            bright_white: ansi[15],
+        };
+        writeln!(self.program, "{}", serde_json::to_string_pretty(&scheme)?)?;
+
+        Ok(())
+    }
+
+    fn compile_xresources(&mut self) -> Result<()> {
+        let TerminalColors {
+            foreground,
+            background,
+            cursor,
+            ..
+        } = self.colors;
+
+        writeln!(self.program, "! {}", self.name)?;
+        writeln!(self.program)?;
+        writeln!(self.program, "*.foreground: {foreground}")?;
+        writeln!(self.program, "*.background: {background}")?;
+        writeln!(self.program, "*.cursorColor: {cursor}")?;
+        for (slot, color) in self.colors.ansi.iter().enumerate() {
+            writeln!(self.program, "*.color{slot}: {color}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compile the light or dark theme of a color scheme to a terminal emulator theme. The theme is
+/// named `<name>-light` or `<name>-dark`.
+pub fn compile(
+    colorscheme: &Colorscheme,
+    emulator: Emulator,
+    background: Kind,
+) -> Result<String, Error> {
+    let name = format!("{}-{background}", colorscheme.name());
+    let mut compiler = Compiler {
+        name: &name,
+        colors: TerminalColors::new(colorscheme, background)?,
+        program: String::new(),
+    };
+
+    let result = match emulator {
+        Emulator::Kitty => compiler.compile_kitty(),
+        Emulator::Alacritty => compiler.compile_alacritty(),
+        Emulator::Wezterm => compiler.compile_wezterm(),
+        Emulator::Foot => compiler.compile_foot(),
+        Emulator::Ghostty => compiler.compile_ghostty(),
+        Emulator::WindowsTerminal => compiler.compile_windows_terminal(),
+        Emulator::Xresources => compiler.compile_xresources(),
+    };
+
+    finish(result.map(|()| compiler.program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bare_hex, compile, Emulator};
+    use crate::configuration::Kind;
+    use crate::terminal::TerminalColors;
+
+    const EMULATORS: [Emulator; 7] = [
+        Emulator::Kitty,
+        Emulator::Alacritty,
+        Emulator::Wezterm,
+        Emulator::Foot,
+        Emulator::Ghostty,
+        Emulator::WindowsTerminal,
+        Emulator::Xresources,
+    ];
+
+    #[test]
+    fn bundled_colorschemes() {
+        for config in [
+            include_str!("../colorschemes/highlow.toml"),
+            include_str!("../colorschemes/verf.toml"),
+            include_str!("../colorschemes/twocolor.toml"),
+            include_str!("../colorschemes/grayscale.toml"),
+        ] {
+            let colorscheme = crate::parse(config).unwrap();
+            for emulator in EMULATORS {
+                for background in [Kind::Light, Kind::Dark] {
+                    assert!(compile(&colorscheme, emulator, background).is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ansi_colors() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let colors = TerminalColors::new(&colorscheme, Kind::Dark).unwrap();
+        // as written to the file, with every line ending in a newline
+        let program = |emulator| compile(&colorscheme, emulator, Kind::Dark).unwrap() + "\n";
+        let names = [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+
+        let kitty = program(Emulator::Kitty);
+        let foot = program(Emulator::Foot);
+        let ghostty = program(Emulator::Ghostty);
+        let xresources = program(Emulator::Xresources);
+        for (slot, &color) in colors.ansi.iter().enumerate() {
+            assert!(kitty.contains(&format!("\ncolor{slot} {color}\n")));
+            let foot_key = match slot {
+                0..=7 => format!("regular{slot}"),
+                _ => format!("bright{}", slot - 8),
+            };
+            assert!(foot.contains(&format!("\n{foot_key}={}\n", bare_hex(color))));
+            assert!(ghostty.contains(&format!("\npalette = {slot}={color}\n")));
+            assert!(xresources.contains(&format!("\n*.color{slot}: {color}\n")));
+        }
+
+        let alacritty: toml::Value = program(Emulator::Alacritty).parse().unwrap();
+        let wezterm: toml::Value = program(Emulator::Wezterm).parse().unwrap();
+        let windows_terminal: serde_json::Value =
+            serde_json::from_str(&program(Emulator::WindowsTerminal)).unwrap();
+        for (slot, name) in names.iter().enumerate() {
+            let (normal, bright) = (colors.normal()[slot], colors.bright()[slot]);
+            let colors = &alacritty["colors"];
+            assert_eq!(colors["normal"][name].as_str(), Some(&*normal.to_string()));
+            assert_eq!(colors["bright"][name].as_str(), Some(&*bright.to_string()));
+            let colors = &wezterm["colors"];
+            assert_eq!(colors["ansi"][slot].as_str(), Some(&*normal.to_string()));
+            assert_eq!(colors["brights"][slot].as_str(), Some(&*bright.to_string()));
+        }
+        assert_eq!(windows_terminal["name"], "highlow-dark");
+        assert_eq!(windows_terminal["brightWhite"], colors.ansi[15].to_string());
+        assert_eq!(windows_terminal["purple"], colors.ansi[5].to_string());
+    }
+}
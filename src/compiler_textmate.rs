@@ -0,0 +1,348 @@
+//! TextMate (`.tmTheme`) and Sublime Text (`.sublime-color-scheme`) color scheme compilation.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::captures::{self, TEXTMATE_SCOPES};
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::compiler_common::finish;
+use crate::configuration::Kind;
+use crate::error::Error;
+
+/// The colors applying to the editor as a whole.
+#[derive(Serialize)]
+struct Globals {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    foreground: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    caret: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    selection: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_highlight: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gutter: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gutter_foreground: Option<Rgb8>,
+}
+
+/// The style of a scope.
+#[derive(Serialize)]
+struct Rule<'a> {
+    /// The highlight group the scope is mapped onto.
+    name: &'a str,
+    scope: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    foreground: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    font_style: Option<String>,
+}
+
+/// The font styles supported. Sublime Text has squiggly and stippled underlines, but no
+/// strikethrough.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    TextMate,
+    Sublime,
+}
+
+fn globals(colorscheme: &Colorscheme, background: Kind) -> Globals {
+    let theme = colorscheme.theme(background);
+    let fg = |group: &str| {
+        let highlight = captures::resolve(colorscheme, group)?;
+        theme.get_color(highlight.fg?)
+    };
+    let bg = |group: &str| {
+        let highlight = captures::resolve(colorscheme, group)?;
+        theme.get_color(highlight.bg?)
+    };
+
+    Globals {
+        foreground: fg("Normal"),
+        background: bg("Normal"),
+        caret: bg("Cursor"),
+        selection: bg("Visual"),
+        line_highlight: bg("CursorLine"),
+        gutter: bg("SignColumn"),
+        gutter_foreground: fg("LineNr"),
+    }
+}
+
+fn rules<'a>(colorscheme: &Colorscheme<'a>, background: Kind, dialect: Dialect) -> Vec<Rule<'a>> {
+    let theme = colorscheme.theme(background);
+    let mut rules = Vec::new();
+
+    for (scope, group) in captures::scope_mapping(colorscheme, "textmate", TEXTMATE_SCOPES) {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+
+        let (curly, dotted) = match dialect {
+            Dialect::TextMate => ("underline", "underline"),
+            Dialect::Sublime => ("squiggly_underline", "stippled_underline"),
+        };
+        let mut font_style = Vec::new();
+        for (set, style) in [
+            (highlight.bold, "bold"),
+            (highlight.italic, "italic"),
+            (highlight.underline, "underline"),
+            (highlight.underdouble, "underline"),
+            (highlight.underdashed, dotted),
+            (highlight.underdotted, dotted),
+            (highlight.undercurl, curly),
+            (
+                highlight
+                    .strikethrough
+                    .filter(|_| dialect == Dialect::TextMate),
+                "strikethrough",
+            ),
+        ] {
+            if set.unwrap_or(false) && !font_style.contains(&style) {
+                font_style.push(style);
+            }
+        }
+
+        let rule = Rule {
+            name: group,
+            scope,
+            foreground: highlight.fg.and_then(|fg| theme.get_color(fg)),
+            background: highlight.bg.and_then(|bg| theme.get_color(bg)),
+            font_style: (!font_style.is_empty()).then(|| font_style.join(" ")),
+        };
+        if rule.foreground.is_some() || rule.background.is_some() || rule.font_style.is_some() {
+            rules.push(rule);
+        }
+    }
+
+    rules
+}
+
+/// Escape text for use in XML, including attribute values.
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+struct Compiler {
+    program: String,
+    indent: u8,
+}
+
+impl Compiler {
+    fn indent(&mut self) {
+        self.indent = self.indent.saturating_add(1);
+    }
+
+    fn dedent(&mut self) {
+        self.indent = self.indent.saturating_sub(1);
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        self.program += &str::repeat("\t", usize::from(self.indent));
+        writeln!(self.program, "{line}")?;
+        Ok(())
+    }
+
+    fn write_entry(&mut self, key: &str, value: impl std::fmt::Display) -> Result<()> {
+        self.write_line(&format!(
+            "<key>{key}</key><string>{}</string>",
+            escape_xml(&value.to_string())
+        ))
+    }
+
+    fn compile_tmtheme(&mut self, name: &str, globals: &Globals, rules: &[Rule]) -> Result<()> {
+        self.write_line(r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        self.write_line(r#"<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">"#)?;
+        self.write_line(r#"<plist version="1.0">"#)?;
+        self.write_line("<dict>")?;
+        self.indent();
+        self.write_entry("name", name)?;
+        self.write_line("<key>settings</key>")?;
+        self.write_line("<array>")?;
+        self.indent();
+
+        self.write_line("<dict>")?;
+        self.indent();
+        self.write_line("<key>settings</key>")?;
+        self.write_line("<dict>")?;
+        self.indent();
+        for (key, color) in [
+            ("foreground", globals.foreground),
+            ("background", globals.background),
+            ("caret", globals.caret),
+            ("selection", globals.selection),
+            ("lineHighlight", globals.line_highlight),
+            ("gutter", globals.gutter),
+            ("gutterForeground", globals.gutter_foreground),
+        ] {
+            if let Some(color) = color {
+                self.write_entry(key, color)?;
+            }
+        }
+        self.dedent();
+        self.write_line("</dict>")?;
+        self.dedent();
+        self.write_line("</dict>")?;
+
+        for rule in rules {
+            self.write_line("<dict>")?;
+            self.indent();
+            self.write_entry("name", rule.name)?;
+            self.write_entry("scope", rule.scope)?;
+            self.write_line("<key>settings</key>")?;
+            self.write_line("<dict>")?;
+            self.indent();
+            if let Some(foreground) = rule.foreground {
+                self.write_entry("foreground", foreground)?;
+            }
+            if let Some(background) = rule.background {
+                self.write_entry("background", background)?;
+            }
+            if let Some(font_style) = &rule.font_style {
+                self.write_entry("fontStyle", font_style)?;
+            }
+            self.dedent();
+            self.write_line("</dict>")?;
+            self.dedent();
+            self.write_line("</dict>")?;
+        }
+
+        self.dedent();
+        self.write_line("</array>")?;
+        self.dedent();
+        self.write_line("</dict>")?;
+        self.write_line("</plist>")?;
+
+        Ok(())
+    }
+}
+
+/// Compile the light or dark theme of a color scheme to a TextMate theme (`.tmTheme`), as read by
+/// TextMate, `bat` and `delta`.
+///
+/// Highlights are mapped onto TextMate scopes, following links to their final groups. The
+/// mapping can be overridden in the configuration's `[scopes.textmate]` section.
+pub fn compile_tmtheme(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    let mut compiler = Compiler {
+        program: String::with_capacity(16_384),
+        indent: 0,
+    };
+
+    let result = compiler.compile_tmtheme(
+        &format!("{}-{background}", colorscheme.name()),
+        &globals(colorscheme, background),
+        &rules(colorscheme, background, Dialect::TextMate),
+    );
+
+    finish(result.map(|()| compiler.program))
+}
+
+#[derive(Serialize)]
+struct SublimeColorScheme<'a> {
+    name: String,
+    globals: Globals,
+    rules: Vec<Rule<'a>>,
+}
+
+/// Compile the light or dark theme of a color scheme to a Sublime Text color scheme
+/// (`.sublime-color-scheme`).
+///
+/// Highlights are mapped onto TextMate scopes, as for [`compile_tmtheme`].
+pub fn compile_sublime(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    let color_scheme = SublimeColorScheme {
+        name: format!("{}-{background}", colorscheme.name()),
+        globals: globals(colorscheme, background),
+        rules: rules(colorscheme, background, Dialect::Sublime),
+    };
+
+    serde_json::to_string_pretty(&color_scheme).map_err(|_| Error::CompilationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{compile_sublime, compile_tmtheme};
+    use crate::configuration::Kind;
+
+    fn config() -> String {
+        format!(
+            "{}\n[scopes.textmate]\n\"keyword\" = \"Comment\"\n\"comment\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        )
+    }
+
+    #[test]
+    fn tmtheme() {
+        let config = config();
+        let colorscheme = crate::parse(&config).unwrap();
+        for background in [Kind::Light, Kind::Dark] {
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                color.unwrap().to_string()
+            };
+            let program = compile_tmtheme(&colorscheme, background).unwrap();
+            // the foreground of the rule of a scope
+            let foreground = |scope: &str| {
+                let (_, rule) = program.split_once(&format!("<string>{scope}</string>"))?;
+                let (_, foreground) = rule.split_once("<key>foreground</key><string>")?;
+                foreground.split_once("</string>").map(|(color, _)| color)
+            };
+
+            assert!(program.starts_with("<?xml"));
+            assert_eq!(
+                program.matches("<dict>").count(),
+                program.matches("</dict>").count()
+            );
+            assert!(program.contains(&format!("<string>highlow-{background}</string>")));
+            assert!(program.contains(&format!(
+                "<key>background</key><string>{}</string>",
+                color("ui.bg")
+            )));
+            // `@keyword.conditional` falls back to `Conditional`, which links to `Statement`
+            assert_eq!(
+                foreground("keyword.control"),
+                Some(&*color("syn.statement"))
+            );
+            // the overrides
+            assert_eq!(foreground("keyword"), Some(&*color("syn.comment")));
+            assert!(!program.contains("<string>comment</string>"));
+        }
+    }
+
+    #[test]
+    fn sublime() {
+        let config = config();
+        let colorscheme = crate::parse(&config).unwrap();
+        for background in [Kind::Light, Kind::Dark] {
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                Value::String(color.unwrap().to_string())
+            };
+            let program = compile_sublime(&colorscheme, background).unwrap();
+            let color_scheme: Value = serde_json::from_str(&program).unwrap();
+            let rules = color_scheme["rules"].as_array().unwrap();
+            let foreground = |scope: &str| {
+                rules
+                    .iter()
+                    .find(|rule| rule["scope"] == scope)
+                    .map(|rule| rule["foreground"].clone())
+            };
+
+            assert_eq!(color_scheme["globals"]["background"], color("ui.bg"));
+            // `@keyword.conditional` falls back to `Conditional`, which links to `Statement`
+            assert_eq!(foreground("keyword.control"), Some(color("syn.statement")));
+            // the overrides
+            assert_eq!(foreground("keyword"), Some(color("syn.comment")));
+            assert_eq!(foreground("comment"), None);
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! Theme compilation for command-line tools.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Rgb8, Theme};
+use crate::compiler_common::finish;
+use crate::configuration::{Highlight, Kind};
+use crate::error::Error;
+
+/// A command-line tool to compile a theme for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    /// fzf colors, as a shell snippet extending `FZF_DEFAULT_OPTS`.
+    Fzf,
+    /// A `[delta]` section, to be included in `.gitconfig`.
+    Delta,
+    /// A lazygit theme, to be merged with lazygit's `config.yml`.
+    Lazygit,
+    /// A dircolors database, setting `LS_COLORS` when evaluated with `dircolors`.
+    Dircolors,
+    /// A tmux status and pane theme, to be sourced in `tmux.conf`.
+    Tmux,
+}
+
+impl Tool {
+    /// The name of the tool, as used in the configuration's `[scopes.<tool>]` section.
+    fn name(self) -> &'static str {
+        match self {
+            Tool::Fzf => "fzf",
+            Tool::Delta => "delta",
+            Tool::Lazygit => "lazygit",
+            Tool::Dircolors => "dircolors",
+            Tool::Tmux => "tmux",
+        }
+    }
+}
+
+/// What a tool's setting takes from its highlight group.
+#[derive(Clone, Copy)]
+enum Channel {
+    /// The foreground color, and the styles.
+    Fg,
+    /// The background color.
+    Bg,
+    /// The foreground and background color, and the styles.
+    Style,
+    /// The foreground or background color, whichever contrasts more with the background color of
+    /// the given group, and the styles.
+    Contrast(&'static str),
+}
+
+/// The default mapping of fzf's colors onto highlight groups. This can be overridden in the
+/// configuration's `[scopes.fzf]` section.
+const FZF: &[(&str, &str, Channel)] = &[
+    ("fg", "Pmenu", Channel::Fg),
+    ("bg", "Pmenu", Channel::Bg),
+    // the matched text, in an accent color that stands out from `fg` and `fg+`
+    ("hl", "Special", Channel::Fg),
+    ("fg+", "PmenuSel", Channel::Fg),
+    ("bg+", "PmenuSel", Channel::Bg),
+    ("hl+", "Special", Channel::Fg),
+    // the query, as what is searched for; `Search` usually only sets a background
+    ("query", "Search", Channel::Contrast("Pmenu")),
+    ("selected-bg", "Visual", Channel::Bg),
+    ("gutter", "Pmenu", Channel::Bg),
+    ("border", "FloatBorder", Channel::Fg),
+    ("separator", "FloatBorder", Channel::Fg),
+    ("scrollbar", "PmenuThumb", Channel::Bg),
+    ("prompt", "Question", Channel::Fg),
+    ("pointer", "Title", Channel::Fg),
+    ("marker", "Title", Channel::Fg),
+    ("info", "Comment", Channel::Fg),
+    ("spinner", "Comment", Channel::Fg),
+    ("header", "Comment", Channel::Fg),
+];
+
+/// The default mapping of delta's styles onto highlight groups. This can be overridden in the
+/// configuration's `[scopes.delta]` section.
+const DELTA: &[(&str, &str, Channel)] = &[
+    ("minus-style", "DiffDelete", Channel::Style),
+    ("minus-emph-style", "DiffDelete", Channel::Style),
+    ("plus-style", "DiffAdd", Channel::Style),
+    ("plus-emph-style", "DiffAdd", Channel::Style),
+    ("line-numbers-minus-style", "diffRemoved", Channel::Fg),
+    ("line-numbers-plus-style", "diffAdded", Channel::Fg),
+    ("line-numbers-zero-style", "LineNr", Channel::Fg),
+    ("file-style", "Title", Channel::Fg),
+];
+
+/// delta's styles of removed and added lines, which keep the syntax highlighting of the code.
+const DELTA_CODE_STYLES: &[&str] = &[
+    "minus-style",
+    "minus-emph-style",
+    "plus-style",
+    "plus-emph-style",
+];
+
+/// The default mapping of lazygit's theme colors onto highlight groups. This can be overridden in
+/// the configuration's `[scopes.lazygit]` section.
+const LAZYGIT: &[(&str, &str, Channel)] = &[
+    ("activeBorderColor", "Title", Channel::Fg),
+    ("inactiveBorderColor", "WinSeparator", Channel::Fg),
+    ("searchingActiveBorderColor", "IncSearch", Channel::Bg),
+    ("optionsTextColor", "Question", Channel::Fg),
+    ("selectedLineBgColor", "Visual", Channel::Bg),
+    ("inactiveViewSelectedLineBgColor", "CursorLine", Channel::Bg),
+    ("cherryPickedCommitFgColor", "diffChanged", Channel::Fg),
+    ("cherryPickedCommitBgColor", "DiffChange", Channel::Bg),
+    ("unstagedChangesColor", "diffRemoved", Channel::Fg),
+    ("defaultFgColor", "Normal", Channel::Fg),
+];
+
+/// The default mapping of tmux's style and color options onto highlight groups. This can be
+/// overridden in the configuration's `[scopes.tmux]` section.
+const TMUX: &[(&str, &str, Channel)] = &[
+    ("status-style", "StatusLine", Channel::Style),
+    ("window-status-current-style", "PmenuSel", Channel::Style),
+    ("pane-border-style", "WinSeparator", Channel::Fg),
+    ("pane-active-border-style", "FloatBorder", Channel::Fg),
+    ("message-style", "Pmenu", Channel::Style),
+    ("message-command-style", "Pmenu", Channel::Style),
+    ("mode-style", "Visual", Channel::Style),
+    ("clock-mode-colour", "Title", Channel::Fg),
+    ("display-panes-active-colour", "Title", Channel::Fg),
+];
+
+/// The entries of the dircolors database, and the theme elements they take their color from. The
+/// flag sets bold text.
+const DIRCOLORS: &[(&str, &str, bool)] = &[
+    ("DIR", "syn.function", true),
+    ("LINK", "syn.special", false),
+    ("ORPHAN", "diagnostics.error", false),
+    ("MISSING", "diagnostics.error", false),
+    ("EXEC", "syn.string", true),
+    ("FIFO", "syn.keyword", false),
+    ("SOCK", "syn.keyword", false),
+    ("DOOR", "syn.keyword", false),
+    ("BLK", "syn.preproc", true),
+    ("CHR", "syn.preproc", true),
+];
+
+/// File extensions of the dircolors database, grouped by the theme element they take their color
+/// from.
+const DIRCOLORS_EXTENSIONS: &[(&str, &[&str])] = &[
+    // archives and packages
+    (
+        "syn.number",
+        &[
+            ".tar", ".tgz", ".gz", ".bz2", ".xz", ".zst", ".zip", ".7z", ".rar", ".deb", ".rpm",
+            ".jar",
+        ],
+    ),
+    // images and videos
+    (
+        "syn.type",
+        &[
+            ".png", ".jpg", ".jpeg", ".gif", ".bmp", ".svg", ".webp", ".tiff", ".ico", ".mp4",
+            ".mkv", ".webm", ".avi", ".mov",
+        ],
+    ),
+    // audio
+    (
+        "syn.constant",
+        &[".mp3", ".flac", ".ogg", ".opus", ".wav", ".m4a"],
+    ),
+    // backups and temporary files
+    ("syn.comment", &["*~", ".bak", ".tmp", ".swp", ".orig"]),
+];
+
+/// A highlight group's colors and styles, with `reverse` applied.
+struct Resolved {
+    fg: Option<Rgb8>,
+    bg: Option<Rgb8>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Resolved {
+    fn new(theme: &Theme, highlight: Highlight) -> Self {
+        let (fg, bg) = match highlight.reverse.unwrap_or(false) {
+            true => (highlight.bg, highlight.fg),
+            false => (highlight.fg, highlight.bg),
+        };
+        Resolved {
+            fg: fg.and_then(|fg| theme.get_color(fg)),
+            bg: bg.and_then(|bg| theme.get_color(bg)),
+            bold: highlight.bold.unwrap_or(false),
+            italic: highlight.italic.unwrap_or(false),
+            underline: [
+                highlight.underline,
+                highlight.undercurl,
+                highlight.underdouble,
+                highlight.underdotted,
+                highlight.underdashed,
+            ]
+            .into_iter()
+            .any(|set| set.unwrap_or(false)),
+        }
+    }
+
+    /// The foreground or background color, whichever contrasts more with `background`.
+    fn contrasting(&self, background: Option<Rgb8>) -> Option<Rgb8> {
+        let contrast = |color: Option<Rgb8>| {
+            color
+                .zip(background)
+                .map(|(color, background)| color.delta_eok(background))
+        };
+        match contrast(self.bg) > contrast(self.fg) {
+            true => self.bg,
+            false => self.fg,
+        }
+    }
+
+    /// The names of the styles that are set.
+    fn styles(&self) -> Vec<&'static str> {
+        [
+            (self.bold, "bold"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+        ]
+        .into_iter()
+        .filter_map(|(set, style)| set.then_some(style))
+        .collect()
+    }
+}
+
+/// A setting of a tool, resolved to colors.
+struct Setting<'a> {
+    key: &'a str,
+    channel: Channel,
+    resolved: Resolved,
+}
+
+impl Setting<'_> {
+    /// The single color of a setting taking the foreground or background color.
+    fn color(&self) -> Option<Rgb8> {
+        match self.channel {
+            Channel::Bg => self.resolved.bg,
+            Channel::Fg | Channel::Style | Channel::Contrast(_) => self.resolved.fg,
+        }
+    }
+}
+
+struct Compiler<'c, 'a> {
+    colorscheme: &'c Colorscheme<'a>,
+    theme: &'c Theme<'a>,
+    name: String,
+    program: String,
+}
+
+impl<'a> Compiler<'_, 'a> {
+    fn resolve(&self, group: &str) -> Option<Resolved> {
+        captures::resolve(self.colorscheme, group)
+            .map(|highlight| Resolved::new(self.theme, highlight))
+    }
+
+    /// The settings of a tool, in the order of the defaults, followed by those only given in the
+    /// configuration. Settings whose highlight group does not exist are left out.
+    fn settings(
+        &self,
+        tool: Tool,
+        defaults: &'static [(&'static str, &'static str, Channel)],
+    ) -> Vec<Setting<'a>> {
+        let pairs: Vec<_> = defaults
+            .iter()
+            .map(|&(key, group, _)| (key, group))
+            .collect();
+        let mapping = captures::scope_mapping(self.colorscheme, tool.name(), &pairs);
+        let position = |key: &str| {
+            defaults
+                .iter()
+                .position(|(default, _, _)| *default == key)
+                .unwrap_or(defaults.len())
+        };
+        let mut keys: Vec<_> = mapping.keys().copied().collect();
+        keys.sort_by_key(|&key| position(key));
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let channel = defaults
+                    .iter()
+                    .find(|(default, _, _)| *default == key)
+                    .map_or(Channel::Fg, |&(_, _, channel)| channel);
+                let mut resolved = self.resolve(mapping[key])?;
+                if let Channel::Contrast(group) = channel {
+                    let background = self.resolve(group).and_then(|group| group.bg);
+                    resolved.fg = resolved.contrasting(background);
+                }
+                Some(Setting {
+                    key,
+                    channel,
+                    resolved,
+                })
+            })
+            .collect()
+    }
+
+    fn header(&mut self) -> Result<()> {
+        writeln!(self.program, "# {}", self.name)?;
+        writeln!(self.program, "# Generated by hi.nvim.rs.")?;
+        writeln!(self.program)?;
+        Ok(())
+    }
+
+    fn compile_fzf(&mut self) -> Result<()> {
+        self.header()?;
+        writeln!(
+            self.program,
+            "export FZF_DEFAULT_OPTS=\"$FZF_DEFAULT_OPTS \\"
+        )?;
+        for setting in self.settings(Tool::Fzf, FZF) {
+            let Some(color) = setting.color() else {
+                continue;
+            };
+            let mut color = vec![color.to_string()];
+            if !matches!(setting.channel, Channel::Bg) {
+                color.extend(setting.resolved.styles().into_iter().map(str::to_owned));
+            }
+            writeln!(
+                self.program,
+                "  --color={}:{} \\",
+                setting.key,
+                color.join(":")
+            )?;
+        }
+        writeln!(self.program, "\"")?;
+        Ok(())
+    }
+
+    fn compile_delta(&mut self, background: Kind) -> Result<()> {
+        self.header()?;
+        writeln!(self.program, "[delta]")?;
+        writeln!(self.program, "\t{background} = true")?;
+        for setting in self.settings(Tool::Delta, DELTA) {
+            let Resolved { fg, bg, .. } = setting.resolved;
+            let mut style = Vec::new();
+            match fg {
+                Some(fg) => style.push(fg.to_string()),
+                None if DELTA_CODE_STYLES.contains(&setting.key) => style.push("syntax".to_owned()),
+                None => style.push("normal".to_owned()),
+            }
+            if let (Channel::Style, Some(bg)) = (setting.channel, bg) {
+                style.push(bg.to_string());
+            }
+            style.extend(setting.resolved.styles().into_iter().map(str::to_owned));
+            // emphasized words within removed and added lines stand out by their weight
+            if setting.key.ends_with("-emph-style") && !setting.resolved.bold {
+                style.push("bold".to_owned());
+            }
+            writeln!(self.program, "\t{} = \"{}\"", setting.key, style.join(" "))?;
+        }
+        Ok(())
+    }
+
+    fn compile_lazygit(&mut self) -> Result<()> {
+        self.header()?;
+        writeln!(self.program, "gui:")?;
+        writeln!(self.program, "  theme:")?;
+        for setting in self.settings(Tool::Lazygit, LAZYGIT) {
+            let Some(color) = setting.color() else {
+                continue;
+            };
+            writeln!(self.program, "    {}:", setting.key)?;
+            writeln!(self.program, "      - \"{color}\"")?;
+            if !matches!(setting.channel, Channel::Bg) {
+                for style in setting.resolved.styles() {
+                    writeln!(self.program, "      - {style}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_dircolors(&mut self) -> Result<()> {
+        self.header()?;
+        let sgr = |element: &str, bold: bool| {
+            self.theme.get_color_by_name(element).map(|color| {
+                let Rgb8 { red, green, blue } = color;
+                let bold = if bold { "1;" } else { "" };
+                format!("{bold}38;2;{red};{green};{blue}")
+            })
+        };
+
+        let mut entries = Vec::new();
+        for &(key, element, bold) in DIRCOLORS {
+            if let Some(sgr) = sgr(element, bold) {
+                entries.push((key, sgr));
+            }
+        }
+        for &(element, extensions) in DIRCOLORS_EXTENSIONS {
+            if let Some(sgr) = sgr(element, false) {
+                entries.extend(extensions.iter().map(|&extension| (extension, sgr.clone())));
+            }
+        }
+
+        for (key, sgr) in entries {
+            writeln!(self.program, "{key} {sgr}")?;
+        }
+        Ok(())
+    }
+
+    fn compile_tmux(&mut self) -> Result<()> {
+        self.header()?;
+        for setting in self.settings(Tool::Tmux, TMUX) {
+            let Resolved { fg, bg, .. } = setting.resolved;
+            let value = if setting.key.ends_with("-colour") {
+                match fg {
+                    Some(fg) => fg.to_string(),
+                    None => continue,
+                }
+            } else {
+                let mut style = Vec::new();
+                if let Some(fg) = fg {
+                    style.push(format!("fg={fg}"));
+                }
+                if let (Channel::Style, Some(bg)) = (setting.channel, bg) {
+                    style.push(format!("bg={bg}"));
+                }
+                style.extend(setting.resolved.styles().into_iter().map(str::to_owned));
+                if style.is_empty() {
+                    continue;
+                }
+                style.join(",")
+            };
+            writeln!(self.program, "set -g {} \"{value}\"", setting.key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compile the light or dark theme of a color scheme to a theme for a command-line tool. The
+/// theme is named `<name>-light` or `<name>-dark`.
+///
+/// Highlights are mapped onto the tool's settings, following links to their final groups. The
+/// mapping can be overridden in the configuration's `[scopes.<tool>]` section (e.g.,
+/// `[scopes.fzf]`). dircolors entries take their colors from the `syn` theme elements instead.
+pub fn compile(colorscheme: &Colorscheme, tool: Tool, background: Kind) -> Result<String, Error> {
+    let mut compiler = Compiler {
+        colorscheme,
+        theme: colorscheme.theme(background),
+        name: format!("{}-{background}", colorscheme.name()),
+        program: String::with_capacity(4_096),
+    };
+
+    let result = match tool {
+        Tool::Fzf => compiler.compile_fzf(),
+        Tool::Delta => compiler.compile_delta(background),
+        Tool::Lazygit => compiler.compile_lazygit(),
+        Tool::Dircolors => compiler.compile_dircolors(),
+        Tool::Tmux => compiler.compile_tmux(),
+    };
+
+    finish(result.map(|()| compiler.program))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, Tool};
+    use crate::configuration::Kind;
+
+    #[test]
+    fn fzf_matches_stand_out() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        for background in [Kind::Light, Kind::Dark] {
+            let program = compile(&colorscheme, Tool::Fzf, background).unwrap();
+            let color = |key: &str| {
+                program
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix(&format!("--color={key}:")))
+                    .map(|color| color.trim_end_matches(" \\").to_owned())
+            };
+            assert!(color("hl").is_some());
+            assert_ne!(color("hl"), color("fg"));
+            assert_ne!(color("hl+"), color("fg+"));
+            // `Search`'s background hardly differs from the menu's, so its foreground is taken
+            let search = colorscheme.highlight("Search").and_then(|search| search.fg);
+            let search = search.and_then(|fg| colorscheme.theme(background).get_color(fg));
+            assert_eq!(color("query"), search.map(|color| color.to_string()));
+        }
+    }
+}
@@ -5,6 +5,7 @@ use anyhow::Result;
 use crate::colorscheme::{Colorscheme, Theme};
 use crate::configuration::Highlight;
 use crate::error::Error;
+use crate::options::CompileOptions;
 
 /// Check whether a highlight group name is a legal name for Vim.
 /// This corresponds to the regexp [a-zA-Z0-9_].
@@ -20,6 +21,7 @@ fn vim_legal_name(name: &str) -> bool {
 
 struct Compiler<'c> {
     colorscheme: &'c Colorscheme<'c>,
+    options: &'c CompileOptions<'c>,
     program: String,
     indent: u8,
 }
@@ -56,6 +58,10 @@ impl<'c> Compiler<'c> {
             }
         }
 
+        if self.options.terminal16.is_some() {
+            return self.compile_terminal16_attributes(name, highlight, theme);
+        }
+
         write!(self.program, "hi {name} term=NONE cterm=NONE")?;
         if let Some(fg) = highlight.fg {
             let color = theme.get_color(fg).unwrap();
@@ -79,17 +85,36 @@ impl<'c> Compiler<'c> {
         }
 
         write!(self.program, " gui=")?;
-        {
-            let mut some = false;
-            for (idx, style) in highlight.gui_styles_iter().enumerate() {
-                some = true;
-                if idx > 0 {
-                    write!(self.program, ",")?;
-                }
-                write!(self.program, "{}", style)?;
-            }
-            if !some {
-                write!(self.program, "NONE")?;
+        self.compile_styles(&highlight)?;
+
+        writeln!(self.program)?;
+
+        Ok(())
+    }
+
+    /// Write the highlight group's attributes for `notermguicolors`, referring to the terminal's
+    /// color slots.
+    fn compile_terminal16_attributes(
+        &mut self,
+        name: &str,
+        highlight: Highlight<'c>,
+        theme: &Theme,
+    ) -> Result<()> {
+        let palette = self.options.terminal16.unwrap();
+
+        write!(self.program, "hi {name} term=NONE cterm=")?;
+        self.compile_styles(&highlight)?;
+
+        for (attribute, element) in [
+            ("ctermfg", highlight.fg),
+            ("ctermbg", highlight.bg),
+            ("ctermul", highlight.sp),
+        ] {
+            if let Some(element) = element {
+                let slot = palette.closest_slot(theme.get_color(element).unwrap());
+                write!(self.program, " {attribute}={slot}")?;
+            } else {
+                write!(self.program, " {attribute}=NONE")?;
             }
         }
 
@@ -98,6 +123,22 @@ impl<'c> Compiler<'c> {
         Ok(())
     }
 
+    fn compile_styles(&mut self, highlight: &Highlight) -> Result<()> {
+        let mut some = false;
+        for (idx, style) in highlight.gui_styles_iter().enumerate() {
+            some = true;
+            if idx > 0 {
+                write!(self.program, ",")?;
+            }
+            write!(self.program, "{}", style)?;
+        }
+        if !some {
+            write!(self.program, "NONE")?;
+        }
+
+        Ok(())
+    }
+
     pub fn compile_highlight_groups(&mut self, theme: &Theme) -> Result<()> {
         for (name, highlight) in &self.colorscheme.highlights {
             if !vim_legal_name(name) {
@@ -107,6 +148,29 @@ impl<'c> Compiler<'c> {
             self.compile_highlight_group(name, *highlight, theme)?;
         }
 
+        self.compile_terminal_colors(theme)?;
+
+        Ok(())
+    }
+
+    /// Set the colors of `:terminal` windows, if the color scheme declares terminal colors.
+    fn compile_terminal_colors(&mut self, theme: &Theme) -> Result<()> {
+        // `notermguicolors` terminals use the terminal's own palette.
+        if self.options.terminal16.is_some() {
+            return Ok(());
+        }
+        let Some(elements) = crate::terminal::ansi_elements(self.colorscheme)? else {
+            return Ok(());
+        };
+
+        let colors = elements
+            .iter()
+            .map(|&element| format!("'{}'", theme.get_color(element).unwrap()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.write_indent();
+        writeln!(self.program, "let g:terminal_ansi_colors = [{colors}]")?;
+
         Ok(())
     }
 }
@@ -114,22 +178,51 @@ impl<'c> Compiler<'c> {
 /// Compile a color scheme to a Neovim configuration.
 // This should probably be infallible.
 pub fn compile(colorscheme: &Colorscheme) -> std::result::Result<String, Error> {
+    compile_with_options(colorscheme, &CompileOptions::default())
+}
+
+/// Compile a color scheme to a Vim configuration, with compilation options.
+pub fn compile_with_options(
+    colorscheme: &Colorscheme,
+    options: &CompileOptions,
+) -> std::result::Result<String, Error> {
     let mut compiler = Compiler {
         colorscheme,
+        options,
         program: String::with_capacity(8192),
         indent: 0,
     };
 
+    // Setting the background reloads the current color scheme, so this must happen first.
+    if let Some(background) = options.forced_background() {
+        writeln!(compiler.program, "set background={background}")
+            .map_err(|_| Error::CompilationFailed)?;
+    }
+
     writeln!(
         compiler.program,
         r#"hi clear
-set termguicolors
+set {termguicolors}
 let g:colors_name = "{name}"
 "#,
-        name = colorscheme.name
+        termguicolors = if options.terminal16.is_some() {
+            "notermguicolors"
+        } else {
+            "termguicolors"
+        },
+        name = options.colors_name(colorscheme),
     )
     .map_err(|_| Error::CompilationFailed)?;
 
+    if let Some(background) = options.background {
+        compiler
+            .compile_highlight_groups(colorscheme.theme(background))
+            .map_err(|_| Error::CompilationFailed)?;
+        // Remove the trailing newline, for consistency with the output for both backgrounds.
+        compiler.program.pop();
+        return Ok(compiler.program);
+    }
+
     writeln!(compiler.program, r#"if &background == "light""#)
         .map_err(|_| Error::CompilationFailed)?;
     compiler.indent();
@@ -146,3 +239,69 @@ let g:colors_name = "{name}"
 
     Ok(compiler.program)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, compile_with_options};
+    use crate::colorscheme::Rgb8;
+    use crate::configuration::Kind;
+    use crate::terminal16::Palette;
+    use crate::CompileOptions;
+
+    /// highlow with its terminal colors declared: red as `diagnostics.error`, black as `ui.bg`
+    /// and the others as `ui.fg`.
+    fn terminal_config() -> String {
+        let mut config = format!(
+            "{}\n[terminal]\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        for name in crate::terminal16::SLOT_NAMES {
+            let element = match name {
+                "black" => "ui.bg",
+                "red" => "diagnostics.error",
+                _ => "ui.fg",
+            };
+            config += &format!("{name} = \"{element}\"\n");
+        }
+        config
+    }
+
+    #[test]
+    fn terminal_colors() {
+        let config = terminal_config();
+        let colorscheme = crate::parse(&config).unwrap();
+        let program = compile(&colorscheme).unwrap();
+
+        let lines: Vec<_> = program
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("let g:terminal_ansi_colors = "))
+            .collect();
+        // set for both the light and the dark background
+        assert_eq!(lines.len(), 2);
+        for (line, background) in lines.into_iter().zip([Kind::Light, Kind::Dark]) {
+            let theme = colorscheme.theme(background);
+            let colors: Vec<_> = line
+                .trim_matches(['[', ']'])
+                .split(", ")
+                .map(|color| color.trim_matches('\''))
+                .collect();
+            assert_eq!(colors.len(), 16);
+            let red = theme.get_color_by_name("diagnostics.error").unwrap();
+            assert_eq!(colors[1], red.to_string());
+        }
+
+        // `notermguicolors` terminals use the terminal's own palette
+        let black = Rgb8 {
+            red: 0,
+            green: 0,
+            blue: 0,
+        };
+        let palette = Palette([black; 16]);
+        let options = CompileOptions {
+            terminal16: Some(&palette),
+            ..CompileOptions::default()
+        };
+        let program = compile_with_options(&colorscheme, &options).unwrap();
+        assert!(!program.contains("terminal_ansi_colors"));
+    }
+}
@@ -0,0 +1,381 @@
+//! VS Code color theme compilation.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::captures::{self, SEMANTIC_TOKEN_SCOPES, TEXTMATE_SCOPES};
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::configuration::{Highlight, Kind};
+use crate::error::Error;
+use crate::terminal::{self, TerminalColors};
+
+/// The mapping of workbench colors onto theme elements. Workbench colors whose theme element is
+/// not defined are left to VS Code's defaults.
+pub(crate) const WORKBENCH_COLORS: &[(&str, &str)] = &[
+    ("activityBar.background", "ui.bg_dim"),
+    ("activityBar.foreground", "ui.fg"),
+    ("diffEditor.insertedLineBackground", "diff.added"),
+    ("diffEditor.removedLineBackground", "diff.removed"),
+    ("dropdown.background", "ui.pmenu_bg"),
+    ("dropdown.foreground", "ui.pmenu_fg"),
+    ("editor.background", "ui.bg"),
+    ("editor.foreground", "ui.fg"),
+    ("editor.lineHighlightBackground", "ui.bg_highlight"),
+    ("editor.selectionBackground", "ui.bg_visual"),
+    ("editorCursor.foreground", "ui.fg"),
+    ("editorError.foreground", "diagnostics.error"),
+    ("editorGroup.border", "ui.bg_border"),
+    ("editorGroupHeader.tabsBackground", "ui.bg_dim"),
+    ("editorGutter.addedBackground", "vcs.added"),
+    ("editorGutter.background", "ui.bg_gutter"),
+    ("editorGutter.deletedBackground", "vcs.removed"),
+    ("editorGutter.modifiedBackground", "vcs.changed"),
+    ("editorHint.foreground", "diagnostics.hint"),
+    ("editorIndentGuide.background1", "ui.bg_highlight"),
+    ("editorInfo.foreground", "diagnostics.info"),
+    ("editorLineNumber.activeForeground", "ui.fg"),
+    ("editorLineNumber.foreground", "ui.nontext"),
+    ("editorSuggestWidget.background", "ui.pmenu_bg"),
+    ("editorSuggestWidget.foreground", "ui.pmenu_fg"),
+    (
+        "editorSuggestWidget.selectedBackground",
+        "ui.pmenu_bg_selected",
+    ),
+    (
+        "editorSuggestWidget.selectedForeground",
+        "ui.pmenu_fg_selected",
+    ),
+    ("editorWarning.foreground", "diagnostics.warning"),
+    ("editorWhitespace.foreground", "ui.nontext"),
+    ("editorWidget.background", "ui.float_bg"),
+    ("editorWidget.border", "ui.float_border"),
+    ("editorWidget.foreground", "ui.float_fg"),
+    ("errorForeground", "diagnostics.error"),
+    ("foreground", "ui.fg"),
+    ("gitDecoration.addedResourceForeground", "vcs.added"),
+    ("gitDecoration.deletedResourceForeground", "vcs.removed"),
+    ("gitDecoration.modifiedResourceForeground", "vcs.changed"),
+    ("input.background", "ui.float_bg"),
+    ("input.foreground", "ui.float_fg"),
+    ("list.activeSelectionBackground", "ui.bg_selected"),
+    ("list.hoverBackground", "ui.bg_highlight"),
+    ("list.inactiveSelectionBackground", "ui.bg_highlight"),
+    ("panel.background", "ui.bg_dim"),
+    ("panel.border", "ui.bg_border"),
+    ("sideBar.background", "ui.bg_dim"),
+    ("sideBar.foreground", "ui.fg"),
+    ("statusBar.background", "ui.bg_dim"),
+    ("statusBar.foreground", "ui.fg_dim"),
+    ("tab.activeBackground", "ui.bg"),
+    ("tab.activeForeground", "ui.fg"),
+    ("tab.inactiveBackground", "ui.bg_dim"),
+    ("tab.inactiveForeground", "ui.fg_dim"),
+    ("titleBar.activeBackground", "ui.bg_dim"),
+    ("titleBar.activeForeground", "ui.fg"),
+];
+
+/// The names of the integrated terminal's ANSI colors, in slot order.
+pub(crate) const TERMINAL_COLORS: [&str; 16] = [
+    "terminal.ansiBlack",
+    "terminal.ansiRed",
+    "terminal.ansiGreen",
+    "terminal.ansiYellow",
+    "terminal.ansiBlue",
+    "terminal.ansiMagenta",
+    "terminal.ansiCyan",
+    "terminal.ansiWhite",
+    "terminal.ansiBrightBlack",
+    "terminal.ansiBrightRed",
+    "terminal.ansiBrightGreen",
+    "terminal.ansiBrightYellow",
+    "terminal.ansiBrightBlue",
+    "terminal.ansiBrightMagenta",
+    "terminal.ansiBrightCyan",
+    "terminal.ansiBrightWhite",
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Theme<'a> {
+    name: String,
+    #[serde(rename = "type")]
+    type_: Kind,
+    semantic_highlighting: bool,
+    colors: BTreeMap<&'a str, Rgb8>,
+    token_colors: Vec<TokenColor<'a>>,
+    semantic_token_colors: BTreeMap<&'a str, SemanticTokenStyle>,
+}
+
+#[derive(Serialize)]
+struct TokenColor<'a> {
+    name: &'a str,
+    scope: &'a str,
+    settings: TokenSettings,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    foreground: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    font_style: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SemanticTokenStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    foreground: Option<Rgb8>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    bold: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    italic: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    underline: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    strikethrough: bool,
+}
+
+/// Whether a highlight draws an underline of any style.
+fn underlined(highlight: &Highlight) -> bool {
+    [
+        highlight.underline,
+        highlight.undercurl,
+        highlight.underdouble,
+        highlight.underdotted,
+        highlight.underdashed,
+    ]
+    .into_iter()
+    .any(|set| set.unwrap_or(false))
+}
+
+/// The file name of a compiled theme (e.g., `highlow-dark.json`).
+fn file_name(colorscheme: &Colorscheme, background: Kind) -> String {
+    format!("{}-{background}.json", colorscheme.name())
+}
+
+/// Compile the light or dark theme of a color scheme to a VS Code color theme.
+///
+/// Highlights are mapped onto TextMate scopes (`tokenColors`) and LSP semantic token types
+/// (`semanticTokenColors`). The workbench colors are taken from the `ui`, `diagnostics`, `vcs` and
+/// `diff` theme elements, and from the terminal colors, if declared.
+pub fn compile(colorscheme: &Colorscheme, background: Kind) -> Result<String, Error> {
+    let theme = colorscheme.theme(background);
+
+    let mut colors: BTreeMap<&str, Rgb8> = WORKBENCH_COLORS
+        .iter()
+        .filter_map(|&(key, element)| Some((key, theme.get_color_by_name(element)?)))
+        .collect();
+
+    if terminal::ansi_elements(colorscheme)?.is_some() {
+        let terminal_colors = TerminalColors::new(colorscheme, background)?;
+        colors.extend(TERMINAL_COLORS.into_iter().zip(terminal_colors.ansi));
+        colors.insert("terminal.foreground", terminal_colors.foreground);
+        colors.insert("terminal.background", terminal_colors.background);
+    }
+
+    let mut token_colors = Vec::new();
+    for (scope, group) in captures::scope_mapping(colorscheme, "textmate", TEXTMATE_SCOPES) {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+
+        let font_style = [
+            (highlight.bold.unwrap_or(false), "bold"),
+            (highlight.italic.unwrap_or(false), "italic"),
+            (underlined(&highlight), "underline"),
+            (highlight.strikethrough.unwrap_or(false), "strikethrough"),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, style)| style)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+        let settings = TokenSettings {
+            foreground: highlight.fg.and_then(|fg| theme.get_color(fg)),
+            background: highlight.bg.and_then(|bg| theme.get_color(bg)),
+            font_style: (!font_style.is_empty()).then_some(font_style),
+        };
+        if settings.foreground.is_some()
+            || settings.background.is_some()
+            || settings.font_style.is_some()
+        {
+            token_colors.push(TokenColor {
+                name: group,
+                scope,
+                settings,
+            });
+        }
+    }
+
+    let mut semantic_token_colors = BTreeMap::new();
+    for (token_type, group) in
+        captures::scope_mapping(colorscheme, "semantic-tokens", SEMANTIC_TOKEN_SCOPES)
+    {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+
+        semantic_token_colors.insert(
+            token_type,
+            SemanticTokenStyle {
+                foreground: highlight.fg.and_then(|fg| theme.get_color(fg)),
+                bold: highlight.bold.unwrap_or(false),
+                italic: highlight.italic.unwrap_or(false),
+                underline: underlined(&highlight),
+                strikethrough: highlight.strikethrough.unwrap_or(false),
+            },
+        );
+    }
+
+    let theme = Theme {
+        name: format!("{}-{background}", colorscheme.name()),
+        type_: background,
+        semantic_highlighting: true,
+        colors,
+        token_colors,
+        semantic_token_colors,
+    };
+
+    serde_json::to_string_pretty(&theme).map_err(|_| Error::CompilationFailed)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Package<'a> {
+    name: String,
+    publisher: &'a str,
+    display_name: &'a str,
+    version: &'a str,
+    engines: Engines<'a>,
+    categories: [&'a str; 1],
+    contributes: Contributes,
+}
+
+#[derive(Serialize)]
+struct Engines<'a> {
+    vscode: &'a str,
+}
+
+#[derive(Serialize)]
+struct Contributes {
+    themes: Vec<ThemeContribution>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ThemeContribution {
+    label: String,
+    ui_theme: &'static str,
+    path: String,
+}
+
+/// Compile a minimal `package.json` for a VS Code extension contributing the color scheme's
+/// compiled themes for the given backgrounds. The themes are expected next to the `package.json`,
+/// in files named `<name>-light.json` and `<name>-dark.json`.
+///
+/// The extension's name is the color scheme's name in lowercase, with anything but letters and
+/// digits replaced by dashes, as extension names may not contain spaces. Its publisher is
+/// `hi-nvim-rs`, which has to be replaced before publishing the extension.
+pub fn compile_package(colorscheme: &Colorscheme, backgrounds: &[Kind]) -> Result<String, Error> {
+    let themes = backgrounds
+        .iter()
+        .map(|&background| ThemeContribution {
+            label: format!("{}-{background}", colorscheme.name()),
+            ui_theme: match background {
+                Kind::Light => "vs",
+                Kind::Dark => "vs-dark",
+            },
+            path: format!("./{}", file_name(colorscheme, background)),
+        })
+        .collect();
+
+    let package = Package {
+        name: colorscheme
+            .name()
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c.to_ascii_lowercase(),
+                false => '-',
+            })
+            .collect(),
+        publisher: "hi-nvim-rs",
+        display_name: colorscheme.name(),
+        version: "0.0.1",
+        engines: Engines { vscode: "^1.43.0" },
+        categories: ["Themes"],
+        contributes: Contributes { themes },
+    };
+
+    serde_json::to_string_pretty(&package).map_err(|_| Error::CompilationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::{compile, compile_package};
+    use crate::configuration::Kind;
+
+    #[test]
+    fn theme() {
+        let config = format!(
+            "{}\n[scopes.textmate]\n\"keyword\" = \"Comment\"\n\
+             [scopes.semantic-tokens]\n\"comment\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+
+        let mut backgrounds = Vec::new();
+        for background in [Kind::Light, Kind::Dark] {
+            let theme: Value =
+                serde_json::from_str(&compile(&colorscheme, background).unwrap()).unwrap();
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                Value::String(color.unwrap().to_string())
+            };
+            let token_color = |scope: &str| {
+                theme["tokenColors"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|token_color| token_color["scope"] == scope)
+                    .map(|token_color| token_color["settings"]["foreground"].clone())
+            };
+
+            assert_eq!(theme["type"], background.to_string());
+            assert_eq!(theme["colors"]["editor.background"], color("ui.bg"));
+            // `@keyword.conditional` falls back to `Conditional`, which links to `Statement`
+            assert_eq!(token_color("keyword.control"), Some(color("syn.statement")));
+            // the overrides
+            assert_eq!(token_color("keyword"), Some(color("syn.comment")));
+            assert!(theme["semanticTokenColors"].get("comment").is_none());
+            assert!(theme["semanticTokenColors"].get("keyword").is_some());
+            backgrounds.push(theme["colors"]["editor.background"].clone());
+        }
+        assert_ne!(backgrounds[0], backgrounds[1]);
+    }
+
+    #[test]
+    fn package() {
+        let config = include_str!("../colorschemes/highlow.toml").replacen(
+            "name = \"highlow\"",
+            "name = \"High Low\"",
+            1,
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let package = compile_package(&colorscheme, &[Kind::Light, Kind::Dark]).unwrap();
+        let package: Value = serde_json::from_str(&package).unwrap();
+
+        assert_eq!(package["name"], "high-low");
+        assert_eq!(package["displayName"], "High Low");
+        assert!(package["publisher"].is_string());
+        let themes = package["contributes"]["themes"].as_array().unwrap();
+        assert_eq!(themes[0]["path"], "./High Low-light.json");
+        assert_eq!(themes[1]["uiTheme"], "vs-dark");
+    }
+}
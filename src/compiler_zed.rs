@@ -0,0 +1,260 @@
+//! Zed theme family compilation.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::captures;
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::configuration::Kind;
+use crate::error::Error;
+use crate::terminal::{self, TerminalColors};
+use crate::terminal16::SLOT_NAMES;
+
+/// The mapping of Zed's interface colors onto theme elements. Colors whose theme element is not
+/// defined are left to Zed's defaults.
+const STYLE_COLORS: &[(&str, &str)] = &[
+    ("background", "ui.bg_dim"),
+    ("border", "ui.bg_border"),
+    ("border.variant", "ui.bg_highlight"),
+    ("created", "vcs.added"),
+    ("deleted", "vcs.removed"),
+    ("editor.active_line.background", "ui.bg_highlight"),
+    ("editor.active_line_number", "ui.fg"),
+    ("editor.background", "ui.bg"),
+    ("editor.foreground", "ui.fg"),
+    ("editor.gutter.background", "ui.bg_gutter"),
+    ("editor.indent_guide", "ui.bg_highlight"),
+    ("editor.indent_guide_active", "ui.bg_selected"),
+    ("editor.invisible", "ui.nontext"),
+    ("editor.line_number", "ui.nontext"),
+    ("editor.wrap_guide", "ui.bg_highlight"),
+    ("element.hover", "ui.bg_highlight"),
+    ("element.selected", "ui.bg_selected"),
+    ("elevated_surface.background", "ui.float_bg"),
+    ("error", "diagnostics.error"),
+    ("ghost_element.hover", "ui.bg_highlight"),
+    ("ghost_element.selected", "ui.bg_selected"),
+    ("hint", "diagnostics.hint"),
+    ("info", "diagnostics.info"),
+    ("modified", "vcs.changed"),
+    ("panel.background", "ui.bg_dim"),
+    ("status_bar.background", "ui.bg_dim"),
+    ("success", "diagnostics.ok"),
+    ("surface.background", "ui.bg_dim"),
+    ("tab.active_background", "ui.bg"),
+    ("tab.inactive_background", "ui.bg_dim"),
+    ("tab_bar.background", "ui.bg_dim"),
+    ("text", "ui.fg"),
+    ("text.accent", "ui.special"),
+    ("text.muted", "ui.fg_dim"),
+    ("text.placeholder", "ui.fg_very_dim"),
+    ("title_bar.background", "ui.bg_dim"),
+    ("toolbar.background", "ui.bg"),
+    ("warning", "diagnostics.warning"),
+];
+
+/// The default mapping of Zed's syntax scopes onto highlight groups and tree-sitter captures. This
+/// can be overridden in the configuration's `[scopes.zed]` section.
+const SCOPES: &[(&str, &str)] = &[
+    ("attribute", "@attribute"),
+    ("boolean", "@boolean"),
+    ("comment", "@comment"),
+    ("comment.doc", "@comment.documentation"),
+    ("constant", "@constant"),
+    ("constructor", "@constructor"),
+    ("emphasis", "@markup.italic"),
+    ("emphasis.strong", "@markup.strong"),
+    ("enum", "@type"),
+    ("function", "@function"),
+    ("keyword", "@keyword"),
+    ("label", "@label"),
+    ("link_text", "@markup.link.label"),
+    ("link_uri", "@markup.link.url"),
+    ("number", "@number"),
+    ("operator", "@operator"),
+    ("preproc", "@keyword.directive"),
+    ("property", "@property"),
+    ("punctuation", "@punctuation"),
+    ("punctuation.bracket", "@punctuation.bracket"),
+    ("punctuation.delimiter", "@punctuation.delimiter"),
+    ("punctuation.list_marker", "@markup.list"),
+    ("punctuation.special", "@punctuation.special"),
+    ("string", "@string"),
+    ("string.escape", "@string.escape"),
+    ("string.regex", "@string.regexp"),
+    ("string.special", "@string.special"),
+    ("string.special.symbol", "@string.special.symbol"),
+    ("tag", "@tag"),
+    ("text.literal", "@markup.raw"),
+    ("title", "@markup.heading"),
+    ("type", "@type"),
+    ("variable", "@variable"),
+    ("variable.special", "@variable.builtin"),
+    ("variant", "@constant"),
+];
+
+#[derive(Serialize)]
+struct ThemeFamily<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'a str,
+    name: &'a str,
+    author: &'a str,
+    themes: Vec<Theme<'a>>,
+}
+
+#[derive(Serialize)]
+struct Theme<'a> {
+    name: String,
+    appearance: Kind,
+    style: Style<'a>,
+}
+
+#[derive(Serialize)]
+struct Style<'a> {
+    #[serde(flatten)]
+    colors: BTreeMap<String, Rgb8>,
+    players: Vec<Player>,
+    syntax: BTreeMap<&'a str, SyntaxStyle>,
+}
+
+#[derive(Serialize)]
+struct Player {
+    cursor: Rgb8,
+    background: Rgb8,
+    selection: Rgb8,
+}
+
+#[derive(Serialize)]
+struct SyntaxStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background_color: Option<Rgb8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    font_style: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    font_weight: Option<u16>,
+}
+
+fn compile_theme<'a>(colorscheme: &Colorscheme<'a>, background: Kind) -> Result<Theme<'a>, Error> {
+    let theme = colorscheme.theme(background);
+
+    let mut colors: BTreeMap<String, Rgb8> = STYLE_COLORS
+        .iter()
+        .filter_map(|&(key, element)| Some((key.to_owned(), theme.get_color_by_name(element)?)))
+        .collect();
+
+    if terminal::ansi_elements(colorscheme)?.is_some() {
+        let terminal_colors = TerminalColors::new(colorscheme, background)?;
+        colors.extend(
+            SLOT_NAMES
+                .iter()
+                .map(|name| format!("terminal.ansi.{name}"))
+                .zip(terminal_colors.ansi),
+        );
+        colors.insert("terminal.foreground".to_owned(), terminal_colors.foreground);
+        colors.insert("terminal.background".to_owned(), terminal_colors.background);
+    }
+
+    let players = match (
+        theme.get_color_by_name("ui.fg"),
+        theme.get_color_by_name("ui.bg_visual"),
+    ) {
+        (Some(cursor), Some(selection)) => vec![Player {
+            cursor,
+            background: cursor,
+            selection,
+        }],
+        _ => Vec::new(),
+    };
+
+    let mut syntax = BTreeMap::new();
+    for (scope, group) in captures::scope_mapping(colorscheme, "zed", SCOPES) {
+        let Some(highlight) = captures::resolve(colorscheme, group) else {
+            continue;
+        };
+
+        syntax.insert(
+            scope,
+            SyntaxStyle {
+                color: highlight.fg.and_then(|fg| theme.get_color(fg)),
+                background_color: highlight.bg.and_then(|bg| theme.get_color(bg)),
+                font_style: highlight.italic.unwrap_or(false).then_some("italic"),
+                font_weight: highlight.bold.unwrap_or(false).then_some(700),
+            },
+        );
+    }
+
+    Ok(Theme {
+        name: format!("{}-{background}", colorscheme.name()),
+        appearance: background,
+        style: Style {
+            colors,
+            players,
+            syntax,
+        },
+    })
+}
+
+/// Compile a color scheme to a Zed theme family, holding a theme for each of the given
+/// backgrounds.
+///
+/// Highlights are mapped onto Zed's syntax scopes. Bold and italic highlights become a
+/// `font_weight` of 700 and a `font_style` of `italic`. Interface colors are taken from the `ui`,
+/// `diagnostics` and `vcs` theme elements, and from the terminal colors, if declared.
+pub fn compile(colorscheme: &Colorscheme, backgrounds: &[Kind]) -> Result<String, Error> {
+    let family = ThemeFamily {
+        schema: "https://zed.dev/schema/themes/v0.2.0.json",
+        name: colorscheme.name(),
+        author: "hi.nvim.rs",
+        themes: backgrounds
+            .iter()
+            .map(|&background| compile_theme(colorscheme, background))
+            .collect::<Result<_, _>>()?,
+    };
+
+    serde_json::to_string_pretty(&family).map_err(|_| Error::CompilationFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::compile;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn theme_family() {
+        let config = format!(
+            "{}\n[scopes.zed]\n\"keyword\" = \"Comment\"\n\"label\" = \"\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let family = compile(&colorscheme, &[Kind::Light, Kind::Dark]).unwrap();
+        let family: Value = serde_json::from_str(&family).unwrap();
+
+        let themes = family["themes"].as_array().unwrap();
+        assert_eq!(themes.len(), 2);
+        for (theme, background) in themes.iter().zip([Kind::Light, Kind::Dark]) {
+            let color = |element| {
+                let color = colorscheme.theme(background).get_color_by_name(element);
+                Value::String(color.unwrap().to_string())
+            };
+            let style = &theme["style"];
+
+            assert_eq!(theme["name"], format!("highlow-{background}"));
+            assert_eq!(theme["appearance"], background.to_string());
+            assert_eq!(style["editor.background"], color("ui.bg"));
+            // `@keyword.directive` falls back to `PreProc`
+            assert_eq!(style["syntax"]["preproc"]["color"], color("syn.preproc"));
+            // the overrides
+            assert_eq!(style["syntax"]["keyword"]["color"], color("syn.comment"));
+            assert!(style["syntax"].get("label").is_none());
+        }
+        assert_ne!(
+            themes[0]["style"]["editor.background"],
+            themes[1]["style"]["editor.background"]
+        );
+    }
+}
@@ -9,14 +9,24 @@ use color::{Oklch, OpaqueColor};
 use serde::Deserialize;
 
 use crate::error::Error;
+use crate::terminal16::Palette;
 use crate::{de::string_or_struct, modifiers::ColorModifiers};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Kind {
     Light,
     Dark,
 }
 
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Kind::Light => "light",
+            Kind::Dark => "dark",
+        })
+    }
+}
+
 impl TryFrom<&str> for Kind {
     type Error = Error;
 
@@ -43,7 +53,7 @@ impl From<Lch> for OpaqueColor<Oklch> {
 /// The `fg`, `bg` and `sp` fields code for specific theme colors. If `Link` is set the highlight
 /// group is linked to a different group, taking the color attributes from that group. The other
 /// fields refer to the highlight `gui` attr-list.
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
 pub struct Highlight<'a> {
     /// A theme color to use as the foreground color (e.g., `syn.function`, `ui.fg_dim`).
     pub fg: Option<NamespacedThemeElement<'a>>,
@@ -121,6 +131,22 @@ struct Configuration_<'a> {
     groups: HashMap<&'a str, HuesConfig>,
     themes: ThemeNamespaces<'a>,
     highlights: BTreeMap<&'a str, Highlight<'a>>,
+    /// The terminal's 16 ANSI colors, used for `notermguicolors` color schemes.
+    #[serde(default)]
+    terminal16: Option<BTreeMap<&'a str, &'a str>>,
+    /// Terminal colors as references to theme elements, overriding the `terminal` theme
+    /// namespace.
+    #[serde(default)]
+    terminal: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    /// base16 palette slots as references to theme elements, overriding the default mapping.
+    #[serde(default)]
+    base16: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    /// Desktop theme slots as references to theme elements, overriding the default mapping.
+    #[serde(default)]
+    desktop: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    /// Per target, overrides of the mapping of the target's scopes onto highlight groups.
+    #[serde(default)]
+    scopes: BTreeMap<&'a str, BTreeMap<&'a str, &'a str>>,
 }
 
 /// Specifier of inverse theme post-processing color modificiations.
@@ -143,7 +169,7 @@ pub struct ThemeElements<'a>(#[serde(borrow)] pub BTreeMap<&'a str, ThemeElement
 #[derive(Debug, Deserialize)]
 pub struct ThemeNamespaces<'a>(#[serde(borrow)] pub BTreeMap<&'a str, ThemeElements<'a>>);
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct ThemeElement<'a> {
     #[serde(borrow)]
     pub color: NamespacedColor<'a>,
@@ -218,7 +244,7 @@ impl Display for NamespacedColor<'_> {
 
 /// An element in a theme namespace (e.g., `syn.type` is `type` in the `syn` namespace). This codes
 /// for a specific color.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct NamespacedThemeElement<'a> {
     pub theme_namespace: &'a str,
     pub element_name: &'a str,
@@ -242,6 +268,11 @@ pub struct Configuration<'a> {
     pub colors: HashMap<NamespacedColor<'a>, OpaqueColor<Oklch>>,
     pub themes: ThemeNamespaces<'a>,
     pub highlights: BTreeMap<&'a str, Highlight<'a>>,
+    pub terminal16: Option<Palette>,
+    pub terminal: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    pub base16: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    pub desktop: BTreeMap<&'a str, NamespacedThemeElement<'a>>,
+    pub scopes: BTreeMap<&'a str, BTreeMap<&'a str, &'a str>>,
 }
 
 impl Configuration<'_> {
@@ -291,6 +322,15 @@ pub fn parse<'a>(config_file: &'a str) -> Result<Configuration<'a>, Error> {
         colors,
         themes: config.themes,
         highlights,
+        terminal16: config
+            .terminal16
+            .as_ref()
+            .map(Palette::from_slot_names)
+            .transpose()?,
+        terminal: config.terminal,
+        base16: config.base16,
+        desktop: config.desktop,
+        scopes: config.scopes,
     };
 
     Ok(config)
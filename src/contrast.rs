@@ -0,0 +1,216 @@
+//! Contrast between the foreground and background colors of highlight groups.
+//!
+//! Two contrast metrics are calculated: the WCAG 2 contrast ratio, and the APCA (Accessible
+//! Perceptual Contrast Algorithm) lightness contrast `Lc`, proposed for WCAG 3.
+
+use serde::Serialize;
+
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::configuration::Kind;
+
+/// The WCAG 2 contrast ratio between two colors, ranging from 1 (no contrast) to 21 (black on
+/// white). This is symmetric in its arguments.
+pub fn wcag2(color1: Rgb8, color2: Rgb8) -> f32 {
+    let l1 = color1.to_srgb().relative_luminance();
+    let l2 = color2.to_srgb().relative_luminance();
+    (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+}
+
+/// The APCA lightness contrast `Lc` of text in color `fg` on background color `bg`, following
+/// APCA-W3 0.0.98G-4g. This ranges from roughly 106 (black text on white) to -108 (white text on
+/// black). The sign indicates the polarity; the magnitude indicates the contrast.
+pub fn apca(fg: Rgb8, bg: Rgb8) -> f32 {
+    const BLACK_THRESHOLD: f32 = 0.022;
+    const BLACK_CLAMP: f32 = 1.414;
+    const DELTA_Y_MIN: f32 = 0.000_5;
+    const SCALE: f32 = 1.14;
+    const LOW_OFFSET: f32 = 0.027;
+    const LOW_CLIP: f32 = 0.1;
+
+    fn screen_luminance(color: Rgb8) -> f32 {
+        let y = 0.212_672_9 * (f32::from(color.red) / 255.).powf(2.4)
+            + 0.715_152_2 * (f32::from(color.green) / 255.).powf(2.4)
+            + 0.072_175 * (f32::from(color.blue) / 255.).powf(2.4);
+
+        // soft clamp near-black colors
+        if y < BLACK_THRESHOLD {
+            y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
+        } else {
+            y
+        }
+    }
+
+    let y_fg = screen_luminance(fg);
+    let y_bg = screen_luminance(bg);
+
+    if (y_bg - y_fg).abs() < DELTA_Y_MIN {
+        return 0.;
+    }
+
+    let lc = if y_bg > y_fg {
+        // dark text on a light background
+        let sapc = (y_bg.powf(0.56) - y_fg.powf(0.57)) * SCALE;
+        if sapc < LOW_CLIP {
+            0.
+        } else {
+            sapc - LOW_OFFSET
+        }
+    } else {
+        // light text on a dark background
+        let sapc = (y_bg.powf(0.65) - y_fg.powf(0.62)) * SCALE;
+        if sapc > -LOW_CLIP {
+            0.
+        } else {
+            sapc + LOW_OFFSET
+        }
+    };
+
+    lc * 100.
+}
+
+/// The minimal contrast highlight groups should have.
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    /// The minimal WCAG 2 contrast ratio (e.g., 4.5 for WCAG AA body text).
+    pub wcag2: f32,
+    /// The minimal absolute APCA lightness contrast (e.g., 60 for APCA body text).
+    pub apca: f32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            wcag2: 4.5,
+            apca: 60.,
+        }
+    }
+}
+
+/// The contrast of a highlight group in one of the themes.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupContrast<'a> {
+    pub group: &'a str,
+    pub background: Kind,
+    /// The group the highlight group links to, if any. The colors are those of that group.
+    pub link: Option<&'a str>,
+    /// The effective foreground color.
+    pub fg: Rgb8,
+    /// The effective background color.
+    pub bg: Rgb8,
+    pub wcag2: f32,
+    pub apca: f32,
+    /// Whether the contrast is below one of the thresholds.
+    pub flagged: bool,
+}
+
+/// Calculate the contrast of all highlight groups in both the light and dark theme.
+///
+/// Groups that link to another group take that group's colors. Groups without a foreground or
+/// background color take the respective color of the `Normal` group, which is what they are drawn
+/// on top of. If the group is reversed, the colors are swapped. Groups that set neither a
+/// foreground nor a background color are skipped, as they do not affect contrast.
+pub fn audit<'a>(colorscheme: &Colorscheme<'a>, thresholds: Thresholds) -> Vec<GroupContrast<'a>> {
+    let normal = colorscheme.highlight("Normal");
+    let mut contrasts = Vec::new();
+
+    for background in [Kind::Light, Kind::Dark] {
+        let theme = colorscheme.theme(background);
+        let normal_fg = normal.and_then(|normal| theme.get_color(normal.fg?));
+        let normal_bg = normal.and_then(|normal| theme.get_color(normal.bg?));
+
+        for (group, highlight) in colorscheme.highlights() {
+            let Some(resolved) = colorscheme.resolved_highlight(group) else {
+                continue;
+            };
+            if resolved.fg.is_none() && resolved.bg.is_none() {
+                continue;
+            }
+
+            let fg = resolved.fg.and_then(|fg| theme.get_color(fg)).or(normal_fg);
+            let bg = resolved.bg.and_then(|bg| theme.get_color(bg)).or(normal_bg);
+            let (Some(mut fg), Some(mut bg)) = (fg, bg) else {
+                continue;
+            };
+            if resolved.reverse.unwrap_or(false) {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+
+            let wcag2 = wcag2(fg, bg);
+            let apca = apca(fg, bg);
+            contrasts.push(GroupContrast {
+                group,
+                background,
+                link: highlight.link,
+                fg,
+                bg,
+                wcag2,
+                apca,
+                flagged: wcag2 < thresholds.wcag2 || apca.abs() < thresholds.apca,
+            });
+        }
+    }
+
+    contrasts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apca, audit, wcag2, Thresholds};
+    use crate::colorscheme::Rgb8;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn known_contrasts() {
+        let black: Rgb8 = "#000000".parse().unwrap();
+        let white: Rgb8 = "#ffffff".parse().unwrap();
+        let gray: Rgb8 = "#888888".parse().unwrap();
+
+        assert!((wcag2(black, white) - 21.).abs() < 0.01);
+        assert!((wcag2(white, black) - 21.).abs() < 0.01);
+        assert!((wcag2(gray, gray) - 1.).abs() < 0.01);
+
+        // reference values from the APCA-W3 reference implementation
+        assert!((apca(black, white) - 106.04).abs() < 0.1);
+        assert!((apca(white, black) - -107.88).abs() < 0.1);
+        assert!((apca(gray, white) - 63.06).abs() < 0.1);
+        assert_eq!(apca(gray, gray), 0.);
+    }
+
+    #[test]
+    fn audit_groups() {
+        let config = include_str!("../colorschemes/highlow.toml").replacen(
+            "[highlights]\n",
+            "[highlights]\n\
+             AuditLink = { link = \"AuditLinked\" }\n\
+             AuditLinked = { fg = \"syn.keyword\" }\n\
+             AuditReverse = { fg = \"syn.keyword\", reverse = true }\n",
+            1,
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let contrasts = audit(&colorscheme, Thresholds::default());
+
+        for background in [Kind::Light, Kind::Dark] {
+            let theme = colorscheme.theme(background);
+            let keyword = theme.get_color_by_name("syn.keyword").unwrap();
+            let normal = colorscheme.highlight("Normal").unwrap();
+            let normal_bg = theme.get_color(normal.bg.unwrap()).unwrap();
+            let contrast = |group: &str| {
+                contrasts
+                    .iter()
+                    .find(|contrast| contrast.group == group && contrast.background == background)
+                    .unwrap()
+            };
+
+            // links are followed, and groups without a background are drawn on `Normal`'s
+            let link = contrast("AuditLink");
+            assert_eq!(link.link, Some("AuditLinked"));
+            assert_eq!((link.fg, link.bg), (keyword, normal_bg));
+            assert_eq!(link.wcag2, wcag2(keyword, normal_bg));
+            assert_eq!(link.flagged, link.wcag2 < 4.5 || link.apca.abs() < 60.);
+
+            // reversed groups swap their colors
+            let reverse = contrast("AuditReverse");
+            assert_eq!((reverse.fg, reverse.bg), (normal_bg, keyword));
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Color-vision-deficiency simulation.
+//!
+//! Colors are simulated using the model of Machado, Oliveira and Fernandes (2009), "A
+//! Physiologically-based Model for Simulation of Color Vision Deficiency", at full severity (i.e.,
+//! dichromacy). The simulation is performed in linear sRGB.
+
+use std::fmt::{self, Display, Formatter};
+
+use color::{LinearSrgb, Oklch, OpaqueColor, Srgb};
+use serde::Serialize;
+
+use crate::colorscheme::{Colorscheme, Rgb8, Theme};
+use crate::configuration::{Kind, NamespacedThemeElement};
+
+/// A color vision deficiency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deficiency {
+    /// Protanopia: missing long-wavelength (red) cones.
+    Protan,
+    /// Deuteranopia: missing medium-wavelength (green) cones.
+    Deutan,
+    /// Tritanopia: missing short-wavelength (blue) cones.
+    Tritan,
+}
+
+impl Deficiency {
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Deficiency::Protan => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            Deficiency::Deutan => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            Deficiency::Tritan => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+        }
+    }
+
+    /// Simulate how a color is perceived with this deficiency.
+    pub fn simulate(self, color: Rgb8) -> Rgb8 {
+        let linear = color.to_srgb().convert::<LinearSrgb>().components;
+        let simulated = self.matrix().map(|row| {
+            (row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2]).clamp(0., 1.)
+        });
+        let [r, g, b] = OpaqueColor::<LinearSrgb>::new(simulated)
+            .convert::<Srgb>()
+            .components;
+
+        Rgb8 {
+            red: (r * 255.).round() as u8,
+            green: (g * 255.).round() as u8,
+            blue: (b * 255.).round() as u8,
+        }
+    }
+
+    /// Simulate how a theme is perceived with this deficiency.
+    pub fn simulate_theme<'a>(self, theme: &Theme<'a>) -> Theme<'a> {
+        let mut theme = theme.clone();
+        for (namespace, elements) in theme.colors.iter_mut() {
+            for (element, color) in elements.iter_mut() {
+                *color = self.simulate(*color);
+                // the simulated colors are in gamut, so they replace the unmapped colors as well
+                if let Some(oklch) = theme
+                    .oklch
+                    .get_mut(namespace)
+                    .and_then(|elements| elements.get_mut(element))
+                {
+                    *oklch = color.to_srgb().convert::<Oklch>();
+                }
+            }
+        }
+        theme
+    }
+
+    /// Simulate how a color scheme is perceived with this deficiency. The resulting color scheme
+    /// can be compiled for previewing.
+    pub fn simulate_colorscheme<'a>(self, colorscheme: &Colorscheme<'a>) -> Colorscheme<'a> {
+        let mut simulated = colorscheme.clone();
+        simulated.light_theme = self.simulate_theme(&colorscheme.light_theme);
+        simulated.dark_theme = self.simulate_theme(&colorscheme.dark_theme);
+        simulated
+    }
+}
+
+impl Display for Deficiency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Deficiency::Protan => "protanopia",
+            Deficiency::Deutan => "deuteranopia",
+            Deficiency::Tritan => "tritanopia",
+        })
+    }
+}
+
+/// Two theme elements that are hard to distinguish with a color vision deficiency.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Confusion<'a> {
+    /// The theme in which the elements are confused.
+    pub background: Kind,
+    pub elements: [NamespacedThemeElement<'a>; 2],
+    /// The perceptual difference between the elements' colors.
+    pub delta_eok: f32,
+    /// The perceptual difference between the elements' colors with the deficiency.
+    pub simulated_delta_eok: f32,
+}
+
+/// Find the semantically paired theme elements that become hard to distinguish with the
+/// deficiency, in both the light and dark theme.
+///
+/// All elements within a namespace listed in `theme_namespaces` are considered paired (e.g.,
+/// `diagnostics.error` and `diagnostics.ok`). Paired elements are reported if their DeltaEOK falls
+/// below `threshold` with the deficiency. Elements that are already closer than `threshold`
+/// without the deficiency are not reported: the deficiency is not what makes them hard to
+/// distinguish.
+pub fn check<'a>(
+    colorscheme: &Colorscheme<'a>,
+    deficiency: Deficiency,
+    theme_namespaces: &[&str],
+    threshold: f32,
+) -> Vec<Confusion<'a>> {
+    let mut confusions = Vec::new();
+
+    for (background, theme) in [
+        (Kind::Light, &colorscheme.light_theme),
+        (Kind::Dark, &colorscheme.dark_theme),
+    ] {
+        let elements: Vec<_> = theme
+            .elements()
+            .filter(|(element, _)| theme_namespaces.contains(&element.theme_namespace))
+            .collect();
+
+        for (idx, &(element1, color1)) in elements.iter().enumerate() {
+            for &(element2, color2) in &elements[idx + 1..] {
+                if element1.theme_namespace != element2.theme_namespace {
+                    continue;
+                }
+
+                let delta_eok = color1.delta_eok(color2);
+                let simulated_delta_eok = deficiency
+                    .simulate(color1)
+                    .delta_eok(deficiency.simulate(color2));
+                if delta_eok >= threshold && simulated_delta_eok < threshold {
+                    confusions.push(Confusion {
+                        background,
+                        elements: [element1, element2],
+                        delta_eok,
+                        simulated_delta_eok,
+                    });
+                }
+            }
+        }
+    }
+
+    confusions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Deficiency};
+    use crate::colorscheme::Rgb8;
+    use crate::configuration::Kind;
+
+    #[test]
+    fn red_green_confusion() {
+        let orange: Rgb8 = "#f0a010".parse().unwrap();
+        let green: Rgb8 = "#80c010".parse().unwrap();
+        let blue: Rgb8 = "#3a5fb0".parse().unwrap();
+
+        assert!(orange.delta_eok(green) > 0.15);
+        for deficiency in [Deficiency::Protan, Deficiency::Deutan] {
+            let (orange, green, blue) = (
+                deficiency.simulate(orange),
+                deficiency.simulate(green),
+                deficiency.simulate(blue),
+            );
+            assert!(orange.delta_eok(green) < 0.05);
+            assert!(orange.delta_eok(blue) > 0.15);
+        }
+    }
+
+    #[test]
+    fn grays_are_preserved() {
+        for deficiency in [Deficiency::Protan, Deficiency::Deutan, Deficiency::Tritan] {
+            for gray in ["#000000", "#7f7f7f", "#ffffff"] {
+                let gray: Rgb8 = gray.parse().unwrap();
+                assert!(deficiency.simulate(gray).delta_eok(gray) < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn check_pairs() {
+        let config = format!(
+            "{}\n[themes.audit]\n\
+             orange = \"fg_accented.orange\"\n\
+             green = \"fg_accented.green\"\n\
+             blue = \"fg_accented.blue\"\n",
+            include_str!("../colorschemes/highlow.toml")
+        );
+        let colorscheme = crate::parse(&config).unwrap();
+        let confusions = check(&colorscheme, Deficiency::Deutan, &["audit"], 0.05);
+
+        // orange and green are confused in both themes, blue stays distinct
+        assert_eq!(confusions.len(), 2);
+        for (confusion, background) in confusions.iter().zip([Kind::Light, Kind::Dark]) {
+            assert_eq!(confusion.background, background);
+            let elements = confusion.elements.map(|element| element.to_string());
+            assert_eq!(elements, ["audit.green", "audit.orange"]);
+            assert!(confusion.delta_eok >= 0.05 && confusion.simulated_delta_eok < 0.05);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Distinguishability of theme element colors.
+//!
+//! Theme elements in the same namespace (e.g., `syn.function` and `syn.parameter`) are meant to be
+//! told apart. If their colors are perceptually too close, they might as well be one color.
+
+use serde::Serialize;
+
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::configuration::{Kind, NamespacedThemeElement};
+
+/// A just-noticeable difference in DeltaEOK.
+pub const JND: f32 = 0.02;
+
+/// Two theme elements with colors that are hard to tell apart.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SimilarPair<'a> {
+    /// The theme in which the elements' colors are similar.
+    pub background: Kind,
+    pub elements: [NamespacedThemeElement<'a>; 2],
+    pub colors: [Rgb8; 2],
+    /// The perceptual difference between the elements' colors.
+    pub delta_eok: f32,
+}
+
+/// Find the pairs of theme elements in `theme_namespace` (e.g., `syn`) whose colors differ by less
+/// than `jnd_multiple` times the [just-noticeable difference](JND), in both the light and dark
+/// theme.
+///
+/// Elements with identical definitions (the same color with the same modifiers) intentionally
+/// share a color, and are not reported.
+pub fn audit<'a>(
+    colorscheme: &Colorscheme<'a>,
+    theme_namespace: &str,
+    jnd_multiple: f32,
+) -> Vec<SimilarPair<'a>> {
+    let threshold = jnd_multiple * JND;
+    let mut pairs = Vec::new();
+
+    for background in [Kind::Light, Kind::Dark] {
+        let elements: Vec<_> = colorscheme
+            .theme(background)
+            .elements()
+            .filter(|(element, _)| element.theme_namespace == theme_namespace)
+            .collect();
+
+        for (idx, &(element1, color1)) in elements.iter().enumerate() {
+            for &(element2, color2) in &elements[idx + 1..] {
+                if colorscheme.same_definition(element1, element2) {
+                    continue;
+                }
+
+                let delta_eok = color1.delta_eok(color2);
+                if delta_eok < threshold {
+                    pairs.push(SimilarPair {
+                        background,
+                        elements: [element1, element2],
+                        colors: [color1, color2],
+                        delta_eok,
+                    });
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{audit, JND};
+    use crate::configuration::{Kind, NamespacedThemeElement};
+
+    fn names(pair: &super::SimilarPair) -> [String; 2] {
+        pair.elements.map(|element| element.to_string())
+    }
+
+    #[test]
+    fn similar_pairs_are_reported() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let comment_and_punctuation = |jnd_multiple| {
+            audit(&colorscheme, "syn", jnd_multiple)
+                .into_iter()
+                .find(|pair| {
+                    pair.background == Kind::Dark
+                        && names(pair) == ["syn.comment", "syn.punctuation"]
+                })
+        };
+
+        // `syn.punctuation` is `syn.comment`, slightly lightened
+        let delta_eok = comment_and_punctuation(f32::INFINITY).unwrap().delta_eok;
+        assert!(delta_eok > 0.);
+        assert!(comment_and_punctuation(delta_eok / JND * 1.01).is_some());
+        assert!(comment_and_punctuation(delta_eok / JND * 0.99).is_none());
+    }
+
+    #[test]
+    fn identical_definitions_are_skipped() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let pairs = audit(&colorscheme, "syn", f32::INFINITY);
+
+        // both are defined as `fg`, and share a color intentionally
+        let element = |element_name| NamespacedThemeElement {
+            theme_namespace: "syn",
+            element_name,
+        };
+        assert!(colorscheme.same_definition(element("identifier"), element("variable")));
+        assert!(!pairs
+            .iter()
+            .any(|pair| names(pair) == ["syn.identifier", "syn.variable"]));
+        assert!(pairs
+            .iter()
+            .all(|pair| !colorscheme.same_definition(pair.elements[0], pair.elements[1])));
+    }
+}
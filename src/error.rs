@@ -10,8 +10,28 @@ pub enum Error {
     ColorMissing(String),
     #[error("A theme element referenced by a highlight group is missing: {0}")]
     ThemeElementMissing(String),
+    #[error("A highlight group referenced by a scope mapping is missing: {0}")]
+    HighlightGroupMissing(String),
     #[error("A link cycle was detected. This highlight group is part of the cycle: {0}")]
     LinkCycle(String),
+    #[error("Invalid hexadecimal color (expected a color like `#3d3d3d`): {0}")]
+    InvalidHexColor(String),
+    #[error("Invalid terminal palette: {0}")]
+    InvalidTerminalPalette(String),
+    #[error(
+        "A terminal color is missing (declare it in `[terminal]` or `[themes.terminal]`): {0}"
+    )]
+    TerminalColorMissing(String),
+    #[error("Unknown terminal color (expected an ANSI color like `bright_red`, or `foreground`, `background`, `cursor` or `selection`): {0}")]
+    UnknownTerminalColor(String),
+    #[error("Unknown base16 palette slot (expected `base00` to `base17`): {0}")]
+    UnknownBase16Slot(String),
+    #[error("Unknown desktop theme slot (expected one of `background`, `foreground`, `foreground_dim`, `surface`, `selection`, `border`, `accent`, `urgent`, `warning`, `info` or `ok`): {0}")]
+    UnknownDesktopSlot(String),
+    #[error("Unknown scope mapping target (expected one of `helix`, `textmate`, `semantic-tokens`, `zed`, `emacs`, `kakoune`, `micro`, `jetbrains`, `pygments`, `fzf`, `delta`, `lazygit` or `tmux`): {0}")]
+    UnknownScopeTarget(String),
+    #[error("Could not import color scheme: {0}")]
+    ImportFailed(String),
     #[error("Color scheme compilation failed for an unknown reason")]
     CompilationFailed,
 }
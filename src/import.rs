@@ -0,0 +1,1186 @@
+//! Import of existing color schemes as hi.nvim.rs configurations.
+//!
+//! An imported color scheme is a set of highlight groups with exact colors. It is converted to a
+//! configuration by merging near-identical colors, and by factoring the remaining colors into hues
+//! and lightness/chroma groups where they line up. Theme elements are inferred from the default
+//! highlights: as the default `Comment` highlight uses `syn.comment` as its foreground, the
+//! imported `Comment` foreground becomes `syn.comment`. The configuration is then compiled again
+//! and compared with the original, to report the colors that changed.
+//!
+//! Palettes (base16 and base24) have no highlight groups; their slots are mapped onto theme
+//! elements directly.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+
+use color::{Oklch, OpaqueColor};
+use serde::Serialize;
+
+use crate::colorscheme::Rgb8;
+use crate::configuration::{Highlight, Kind, NamespacedThemeElement};
+use crate::error::Error;
+use crate::terminal16::SLOT_NAMES;
+
+pub use crate::import_base16::parse as parse_base16;
+pub use crate::import_textmate::{parse_tmtheme, parse_vscode};
+pub use crate::import_vim::parse as parse_vim;
+
+/// Colors that differ by less than this DeltaEOK are merged into one color.
+pub const MERGE_DELTA_EOK: f32 = 0.01;
+
+/// Colors with a chroma below this are considered grey, and are not factored into hues.
+const ACHROMATIC: f32 = 0.02;
+
+/// The maximal difference in hue (in degrees) between colors sharing a hue.
+const HUE_TOLERANCE: f32 = 8.;
+
+/// The maximal differences in lightness and chroma between colors sharing a group.
+const LIGHTNESS_TOLERANCE: f32 = 0.02;
+const CHROMA_TOLERANCE: f32 = 0.015;
+
+/// Names for hues, with their approximate hue in Oklch.
+const HUE_NAMES: &[(&str, f32)] = &[
+    ("red", 25.),
+    ("orange", 55.),
+    ("yellow", 100.),
+    ("green", 140.),
+    ("cyan", 200.),
+    ("blue", 255.),
+    ("purple", 300.),
+    ("pink", 345.),
+];
+
+/// The accent slots of base16 (`base08` to `base0F`) and base24 (`base12` to `base17`) palettes,
+/// with the names of the hues they are factored into.
+const BASE16_ACCENTS: &[(usize, &str)] = &[
+    (0x08, "red"),
+    (0x09, "orange"),
+    (0x0a, "yellow"),
+    (0x0b, "green"),
+    (0x0c, "cyan"),
+    (0x0d, "blue"),
+    (0x0e, "purple"),
+    (0x0f, "brown"),
+    (0x12, "bright_red"),
+    (0x13, "bright_yellow"),
+    (0x14, "bright_green"),
+    (0x15, "bright_cyan"),
+    (0x16, "bright_blue"),
+    (0x17, "bright_purple"),
+];
+
+/// The styles a highlight group can set, as named in the configuration.
+pub const STYLES: [&str; 10] = [
+    "bold",
+    "italic",
+    "underline",
+    "undercurl",
+    "underdouble",
+    "underdotted",
+    "underdashed",
+    "strikethrough",
+    "reverse",
+    "nocombine",
+];
+
+/// A highlight group of an imported color scheme.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportedHighlight {
+    pub fg: Option<Rgb8>,
+    pub bg: Option<Rgb8>,
+    pub sp: Option<Rgb8>,
+    /// If set, this highlight group is linked to a different group. The other fields are ignored.
+    pub link: Option<String>,
+    /// The styles that are set (e.g., `bold`), named as in [`STYLES`].
+    pub styles: BTreeSet<&'static str>,
+}
+
+/// A color an imported color scheme gives for a theme element directly, rather than through a
+/// highlight group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedColor {
+    /// Where the color comes from (e.g., the VS Code workbench color `editor.background`).
+    pub source: String,
+    /// The theme element the color is given for (e.g., `ui.bg`).
+    pub element: String,
+    pub color: Rgb8,
+}
+
+/// An imported color scheme.
+#[derive(Debug, Clone, Default)]
+pub struct Imported {
+    /// The name of the color scheme, if it declares one.
+    pub name: Option<String>,
+    pub highlights: BTreeMap<String, ImportedHighlight>,
+    /// Colors given for theme elements directly. Like the colors of highlight groups, these vote
+    /// for the color of their element.
+    pub colors: Vec<ImportedColor>,
+    /// The terminal's 16 ANSI colors in slot order, if the color scheme sets them.
+    pub terminal: Option<[Rgb8; 16]>,
+    /// Problems encountered while importing (e.g., colors that could not be parsed).
+    pub warnings: Vec<String>,
+}
+
+impl Imported {
+    /// The highlight of a group, following links. Returns `None` if the group (or a group it links
+    /// to) is not defined.
+    pub fn resolve(&self, group: &str) -> Option<&ImportedHighlight> {
+        let mut highlight = self.highlights.get(group)?;
+        // bounded, in case the links form a cycle
+        for _ in 0..self.highlights.len() {
+            match &highlight.link {
+                Some(link) => highlight = self.highlights.get(link)?,
+                None => return Some(highlight),
+            }
+        }
+        None
+    }
+}
+
+/// An imported base16 or base24 palette.
+#[derive(Debug, Clone, Default)]
+pub struct Base16 {
+    /// The name of the palette, if it declares one.
+    pub name: Option<String>,
+    /// The colors in slot order: `base00` to `base0F`, followed by `base10` to `base17` for a
+    /// base24 palette.
+    pub colors: Vec<Rgb8>,
+    /// Problems encountered while importing (e.g., colors that could not be parsed).
+    pub warnings: Vec<String>,
+}
+
+/// A color of an imported highlight group that differs from the original after conversion.
+#[derive(Debug, Clone, Serialize)]
+pub struct Difference {
+    /// The highlight group, or where a color given directly comes from (e.g., the palette slot
+    /// `base08`).
+    pub group: String,
+    /// The attribute the color is used for: `fg`, `bg` or `sp`, or `color` for a color given
+    /// directly.
+    pub attribute: &'static str,
+    pub original: Rgb8,
+    pub converted: Rgb8,
+    /// The perceptual difference between the original and converted color.
+    pub delta_eok: f32,
+}
+
+/// An imported color scheme converted to a configuration.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// The configuration, in TOML.
+    pub configuration: String,
+    /// The number of distinct colors in the original color scheme.
+    pub original_colors: usize,
+    /// The number of distinct colors after merging near-identical colors.
+    pub colors: usize,
+    /// The colors of highlight groups that differ from the original, from most to least
+    /// different.
+    pub differences: Vec<Difference>,
+    /// Problems encountered while importing and converting.
+    pub warnings: Vec<String>,
+}
+
+/// Near-identical colors, merged into the most used of them.
+struct Cluster {
+    color: Rgb8,
+    oklch: [f32; 3],
+    members: Vec<Rgb8>,
+    fg_uses: usize,
+    bg_uses: usize,
+}
+
+/// Colors sharing a hue.
+struct Hue {
+    name: String,
+    hue: f32,
+}
+
+/// Colors sharing a lightness and chroma.
+struct Group {
+    name: String,
+    lightness: f32,
+    chroma: f32,
+    members: Vec<usize>,
+}
+
+fn oklch(color: Rgb8) -> [f32; 3] {
+    let [l, c, h] = color.to_srgb().convert::<Oklch>().components;
+    // the hue of a grey is numerical noise
+    let h = if h.is_finite() && c >= 1e-4 {
+        h.rem_euclid(360.)
+    } else {
+        0.
+    };
+    [l, c, h]
+}
+
+fn hue_distance(hue1: f32, hue2: f32) -> f32 {
+    let distance = (hue1 - hue2).rem_euclid(360.);
+    distance.min(360. - distance)
+}
+
+fn round(value: f32, decimals: i32) -> f32 {
+    let factor = 10f32.powi(decimals);
+    (value * factor).round() / factor
+}
+
+/// A name based on `base` that is not yet taken (e.g., `blue_2` if `blue` is taken).
+fn unique_name(base: &str, taken: &mut BTreeSet<String>) -> String {
+    let mut name = base.to_owned();
+    let mut suffix = 1;
+    while taken.contains(&name) {
+        suffix += 1;
+        name = format!("{base}_{suffix}");
+    }
+    taken.insert(name.clone());
+    name
+}
+
+/// The name of the hue closest to `hue`.
+fn hue_name(hue: f32) -> &'static str {
+    HUE_NAMES
+        .iter()
+        .min_by(|(_, hue1), (_, hue2)| {
+            hue_distance(hue, *hue1).total_cmp(&hue_distance(hue, *hue2))
+        })
+        .map(|(name, _)| *name)
+        .unwrap()
+}
+
+/// A TOML key, quoted if necessary.
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_owned()
+    } else {
+        format!("{key:?}")
+    }
+}
+
+fn attribute_color(highlight: &ImportedHighlight, attribute: &str) -> Option<Rgb8> {
+    match attribute {
+        "fg" => highlight.fg,
+        "bg" => highlight.bg,
+        _ => highlight.sp,
+    }
+}
+
+fn attribute_element<'a>(
+    highlight: &Highlight<'a>,
+    attribute: &str,
+) -> Option<NamespacedThemeElement<'a>> {
+    match attribute {
+        "fg" => highlight.fg,
+        "bg" => highlight.bg,
+        _ => highlight.sp,
+    }
+}
+
+/// Merge near-identical colors. Colors are visited in the given order, so earlier colors become
+/// the representatives of the colors merged into them.
+fn merge_colors(uses: &[(Rgb8, usize, usize)]) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for &(color, fg_uses, bg_uses) in uses {
+        match clusters
+            .iter_mut()
+            .find(|cluster| cluster.color.delta_eok(color) < MERGE_DELTA_EOK)
+        {
+            Some(cluster) => {
+                cluster.members.push(color);
+                cluster.fg_uses += fg_uses;
+                cluster.bg_uses += bg_uses;
+            }
+            None => clusters.push(Cluster {
+                color,
+                oklch: oklch(color),
+                members: vec![color],
+                fg_uses,
+                bg_uses,
+            }),
+        }
+    }
+    clusters
+}
+
+/// Per color, the indices of the group and hue it is expressed as, if any.
+type Factoring = Vec<Option<(usize, usize)>>;
+
+/// Factor colors into hues and groups. Returns the hues, the groups, and per color the group and
+/// hue it is expressed as, if any. Colors are only expressed as a hue in a group if that is
+/// (nearly) the same color, and groups are only kept if they hold colors of multiple hues.
+fn factor_colors(clusters: &[Cluster]) -> (Vec<Hue>, Vec<Group>, Factoring) {
+    let chromatic: Vec<usize> = (0..clusters.len())
+        .filter(|&idx| clusters[idx].oklch[1] >= ACHROMATIC)
+        .collect();
+
+    let mut hues: Vec<Hue> = Vec::new();
+    let mut hue_of = vec![0; clusters.len()];
+    for &idx in &chromatic {
+        let hue = clusters[idx].oklch[2];
+        hue_of[idx] = match hues
+            .iter()
+            .position(|other| hue_distance(other.hue, hue) <= HUE_TOLERANCE)
+        {
+            Some(hue_idx) => hue_idx,
+            None => {
+                hues.push(Hue {
+                    name: String::new(),
+                    hue: round(hue, 2),
+                });
+                hues.len() - 1
+            }
+        };
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    for &idx in &chromatic {
+        let [lightness, chroma, _] = clusters[idx].oklch;
+        match groups.iter_mut().find(|group| {
+            (group.lightness - lightness).abs() <= LIGHTNESS_TOLERANCE
+                && (group.chroma - chroma).abs() <= CHROMA_TOLERANCE
+        }) {
+            Some(group) => group.members.push(idx),
+            None => groups.push(Group {
+                name: String::new(),
+                lightness,
+                chroma,
+                members: vec![idx],
+            }),
+        }
+    }
+
+    let mut factored = vec![None; clusters.len()];
+    let mut kept_groups = Vec::new();
+    for mut group in groups {
+        let count = group.members.len() as f32;
+        group.lightness = round(
+            group
+                .members
+                .iter()
+                .map(|&idx| clusters[idx].oklch[0])
+                .sum::<f32>()
+                / count,
+            4,
+        );
+        group.chroma = round(
+            group
+                .members
+                .iter()
+                .map(|&idx| clusters[idx].oklch[1])
+                .sum::<f32>()
+                / count,
+            4,
+        );
+
+        group.members.retain(|&idx| {
+            let color =
+                OpaqueColor::<Oklch>::new([group.lightness, group.chroma, hues[hue_of[idx]].hue]);
+            Rgb8::from(color).delta_eok(clusters[idx].color) < MERGE_DELTA_EOK
+        });
+        let distinct_hues: BTreeSet<usize> = group.members.iter().map(|&idx| hue_of[idx]).collect();
+        if distinct_hues.len() < 2 {
+            continue;
+        }
+
+        for &idx in &group.members {
+            factored[idx] = Some((kept_groups.len(), hue_of[idx]));
+        }
+        kept_groups.push(group);
+    }
+
+    (hues, kept_groups, factored)
+}
+
+/// Convert an imported color scheme to a configuration named `name`.
+///
+/// Highlight groups are mapped onto theme elements where the default highlights use those
+/// elements; other colors are declared in the `palette` theme namespace. Theme elements the
+/// imported color scheme gives no color for fall back to the `Normal` foreground or background.
+pub fn convert(imported: &Imported, name: &str) -> Result<Conversion, Error> {
+    if imported.highlights.is_empty() {
+        return Err(Error::ImportFailed(
+            "no highlight groups were found".to_owned(),
+        ));
+    }
+    let mut warnings = imported.warnings.clone();
+
+    let normal = imported.resolve("Normal");
+    let normal_fg = normal.and_then(|highlight| highlight.fg);
+    let normal_bg = normal.and_then(|highlight| highlight.bg);
+    let kind = match (normal_bg, normal_fg) {
+        (Some(bg), _) if oklch(bg)[0] >= 0.5 => Kind::Light,
+        (None, Some(fg)) if oklch(fg)[0] < 0.5 => Kind::Light,
+        _ => Kind::Dark,
+    };
+    let (default_fg, default_bg) = match kind {
+        Kind::Light => (
+            Rgb8 {
+                red: 0,
+                green: 0,
+                blue: 0,
+            },
+            Rgb8 {
+                red: 255,
+                green: 255,
+                blue: 255,
+            },
+        ),
+        Kind::Dark => (
+            Rgb8 {
+                red: 255,
+                green: 255,
+                blue: 255,
+            },
+            Rgb8 {
+                red: 0,
+                green: 0,
+                blue: 0,
+            },
+        ),
+    };
+    if normal_fg.is_none() {
+        warnings.push(format!(
+            "the `Normal` group has no foreground color; using {default_fg}"
+        ));
+    }
+    if normal_bg.is_none() {
+        warnings.push(format!(
+            "the `Normal` group has no background color; using {default_bg}"
+        ));
+    }
+    let normal_fg = normal_fg.unwrap_or(default_fg);
+    let normal_bg = normal_bg.unwrap_or(default_bg);
+
+    let defaults: BTreeMap<&str, &Highlight> = crate::DEFAULT_HIGHLIGHTS
+        .iter()
+        .map(|(group, highlight)| (*group, highlight))
+        .collect();
+    let background_elements: BTreeSet<String> = defaults
+        .values()
+        .filter_map(|highlight| highlight.bg)
+        .map(|element| element.to_string())
+        .collect();
+
+    // count how often colors are used, as foreground or background
+    let mut uses: Vec<(Rgb8, usize, usize)> = Vec::new();
+    let mut use_color = |color: Rgb8, fg_uses: usize, bg_uses: usize| match uses
+        .iter_mut()
+        .find(|(other, _, _)| *other == color)
+    {
+        Some((_, fg, bg)) => {
+            *fg += fg_uses;
+            *bg += bg_uses;
+        }
+        None => uses.push((color, fg_uses, bg_uses)),
+    };
+    use_color(normal_bg, 0, 1);
+    use_color(normal_fg, 1, 0);
+    for highlight in imported.highlights.values().filter(|h| h.link.is_none()) {
+        for (color, fg_uses, bg_uses) in [
+            (highlight.fg, 1, 0),
+            (highlight.bg, 0, 1),
+            (highlight.sp, 1, 0),
+        ] {
+            if let Some(color) = color {
+                use_color(color, fg_uses, bg_uses);
+            }
+        }
+    }
+    for &color in imported.terminal.iter().flatten() {
+        use_color(color, 1, 0);
+    }
+    for color in &imported.colors {
+        if background_elements.contains(&color.element) {
+            use_color(color.color, 0, 1);
+        } else {
+            use_color(color.color, 1, 0);
+        }
+    }
+    // the `Normal` colors go first, so they are kept as they are
+    let normal_colors = if normal_fg == normal_bg { 1 } else { 2 };
+    uses[normal_colors..]
+        .sort_by_key(|&(_, fg_uses, bg_uses)| std::cmp::Reverse(fg_uses + bg_uses));
+
+    let clusters = merge_colors(&uses);
+    let cluster_of = |color: Rgb8| {
+        clusters
+            .iter()
+            .position(|cluster| cluster.members.contains(&color))
+            .unwrap()
+    };
+    let (mut hues, mut groups, factored) = factor_colors(&clusters);
+
+    // name the hues, groups and colors
+    let mut taken = BTreeSet::new();
+    let used_hues: BTreeSet<usize> = factored.iter().flatten().map(|&(_, hue)| hue).collect();
+    for &hue_idx in &used_hues {
+        hues[hue_idx].name = unique_name(hue_name(hues[hue_idx].hue), &mut taken);
+    }
+    let mut taken = BTreeSet::new();
+    for group in &mut groups {
+        let (fg_uses, bg_uses) = group.members.iter().fold((0, 0), |(fg, bg), &idx| {
+            (fg + clusters[idx].fg_uses, bg + clusters[idx].bg_uses)
+        });
+        group.name = unique_name(if fg_uses >= bg_uses { "fg" } else { "bg" }, &mut taken);
+    }
+    let mut taken = BTreeSet::new();
+    let mut colors = Vec::new();
+    let references: Vec<String> = clusters
+        .iter()
+        .enumerate()
+        .map(|(idx, cluster)| {
+            if let Some((group, hue)) = factored[idx] {
+                return format!("{}.{}", groups[group].name, hues[hue].name);
+            }
+            let base = if idx == cluster_of(normal_bg) {
+                "bg"
+            } else if idx == cluster_of(normal_fg) {
+                "fg"
+            } else if cluster.oklch[1] >= ACHROMATIC {
+                hue_name(cluster.oklch[2])
+            } else {
+                "grey"
+            };
+            let name = unique_name(base, &mut taken);
+            colors.push((name.clone(), cluster.oklch));
+            name
+        })
+        .collect();
+
+    // infer the theme elements from the groups the default highlights use them for
+    let mut votes: BTreeMap<String, BTreeMap<usize, usize>> = BTreeMap::new();
+    for (&group, highlight) in &defaults {
+        for attribute in ["fg", "bg", "sp"] {
+            let Some(element) = attribute_element(highlight, attribute) else {
+                continue;
+            };
+            let votes = votes.entry(element.to_string()).or_default();
+            if let Some(color) = imported
+                .resolve(group)
+                .and_then(|highlight| attribute_color(highlight, attribute))
+            {
+                *votes.entry(cluster_of(color)).or_default() += 1;
+            }
+        }
+    }
+    for color in &imported.colors {
+        *votes
+            .entry(color.element.clone())
+            .or_default()
+            .entry(cluster_of(color.color))
+            .or_default() += 1;
+    }
+    let unvoted: Vec<&str> = votes
+        .iter()
+        .filter(|(element, votes)| {
+            votes.is_empty() && !["ui.fg", "ui.bg"].contains(&element.as_str())
+        })
+        .map(|(element, _)| element.as_str())
+        .collect();
+    if !unvoted.is_empty() {
+        warnings.push(format!(
+            "no colors were found for {} theme elements, which take the `Normal` colors: {}",
+            unvoted.len(),
+            unvoted.join(", ")
+        ));
+    }
+    let mut elements: BTreeMap<String, usize> = votes
+        .into_iter()
+        .map(|(element, votes)| {
+            let cluster = votes
+                .into_iter()
+                .max_by(|(idx1, votes1), (idx2, votes2)| votes1.cmp(votes2).then(idx2.cmp(idx1)))
+                .map(|(idx, _)| idx)
+                .unwrap_or_else(|| {
+                    if background_elements.contains(&element) {
+                        cluster_of(normal_bg)
+                    } else {
+                        cluster_of(normal_fg)
+                    }
+                });
+            (element, cluster)
+        })
+        .collect();
+    // the `Normal` group defines the foreground and background, whatever other groups use
+    elements.insert("ui.fg".to_owned(), cluster_of(normal_fg));
+    elements.insert("ui.bg".to_owned(), cluster_of(normal_bg));
+    if let Some(terminal) = &imported.terminal {
+        for (name, &color) in SLOT_NAMES.iter().zip(terminal) {
+            elements.insert(format!("terminal.{name}"), cluster_of(color));
+        }
+    }
+
+    // the imported highlights, referring to the inferred theme elements where they match
+    let mut palette = BTreeSet::new();
+    let mut highlights = Vec::new();
+    for (group, highlight) in &imported.highlights {
+        if let Some(link) = &highlight.link {
+            highlights.push((group, format!("{{ link = {link:?} }}")));
+            continue;
+        }
+
+        let mut attributes = Vec::new();
+        for attribute in ["fg", "bg", "sp"] {
+            let Some(color) = attribute_color(highlight, attribute) else {
+                continue;
+            };
+            let cluster = cluster_of(color);
+            let element = defaults
+                .get(group.as_str())
+                .and_then(|default| attribute_element(default, attribute))
+                .map(|element| element.to_string())
+                .filter(|element| elements.get(element) == Some(&cluster))
+                .unwrap_or_else(|| {
+                    palette.insert(cluster);
+                    format!("palette.{}", references[cluster].replace('.', "_"))
+                });
+            attributes.push(format!("{attribute} = {element:?}"));
+        }
+        for style in &highlight.styles {
+            attributes.push(format!("{style} = true"));
+        }
+
+        if attributes.is_empty() {
+            highlights.push((group, "{}".to_owned()));
+        } else {
+            highlights.push((group, format!("{{ {} }}", attributes.join(", "))));
+        }
+    }
+
+    let mut themes: BTreeMap<&str, BTreeMap<&str, String>> = BTreeMap::new();
+    for (element, &cluster) in &elements {
+        let (namespace, name) = element.split_once('.').unwrap();
+        themes
+            .entry(namespace)
+            .or_default()
+            .insert(name, format!("{:?}", references[cluster]));
+    }
+    let palette: Vec<(String, &str)> = palette
+        .into_iter()
+        .map(|cluster| {
+            (
+                references[cluster].replace('.', "_"),
+                references[cluster].as_str(),
+            )
+        })
+        .collect();
+    for (name, reference) in &palette {
+        themes
+            .entry("palette")
+            .or_default()
+            .insert(name, format!("{reference:?}"));
+    }
+
+    let configuration = write_configuration(
+        "Imported by hi.nvim.rs. Near-identical colors of the original color scheme\n\
+         have been merged, and colors are factored into hues and groups where they\n\
+         line up.",
+        name,
+        kind,
+        &hues,
+        &used_hues,
+        &groups,
+        &colors,
+        &themes,
+        &highlights,
+    )
+    .map_err(|_| Error::CompilationFailed)?;
+
+    // compile the configuration, and compare it with the original
+    let colorscheme = crate::parse(&configuration)?;
+    let theme = colorscheme.theme(kind);
+    let mut differences = Vec::new();
+    for (group, _) in imported.highlights.iter().filter(|(_, h)| h.link.is_none()) {
+        let (Some(original), Some(converted)) = (
+            imported.resolve(group),
+            colorscheme.resolved_highlight(group),
+        ) else {
+            continue;
+        };
+        for attribute in ["fg", "bg", "sp"] {
+            let original = attribute_color(original, attribute);
+            let converted = attribute_element(&converted, attribute)
+                .and_then(|element| theme.get_color(element));
+            if let (Some(original), Some(converted)) = (original, converted) {
+                if original != converted {
+                    differences.push(Difference {
+                        group: group.clone(),
+                        attribute,
+                        original,
+                        converted,
+                        delta_eok: original.delta_eok(converted),
+                    });
+                }
+            }
+        }
+    }
+    for color in &imported.colors {
+        if let Some(converted) = theme.get_color_by_name(&color.element) {
+            if converted != color.color {
+                differences.push(Difference {
+                    group: color.source.clone(),
+                    attribute: "color",
+                    original: color.color,
+                    converted,
+                    delta_eok: color.color.delta_eok(converted),
+                });
+            }
+        }
+    }
+    differences.sort_by(|difference1, difference2| {
+        difference2.delta_eok.total_cmp(&difference1.delta_eok)
+    });
+
+    Ok(Conversion {
+        configuration,
+        original_colors: uses.len(),
+        colors: clusters.len(),
+        differences,
+        warnings,
+    })
+}
+
+/// Convert an imported base16 or base24 palette to a configuration named `name`.
+///
+/// `base00` to `base07` are ordered by lightness, from the background to the foreground, and
+/// mapped onto the `ui` elements following the base16 styling guidelines (e.g., the third
+/// lightest background of a dark palette is the selection background). The accents are factored
+/// into hues and lightness/chroma groups where they line up, and mapped onto the `syn`,
+/// `diagnostics`, `vcs`, `diff` and `terminal` elements. Every color of the palette is also
+/// declared in the `palette` theme namespace (e.g., `palette.base08`).
+pub fn convert_base16(palette: &Base16, name: &str) -> Result<Conversion, Error> {
+    let slots = palette.colors.len();
+    if slots < 16 {
+        return Err(Error::ImportFailed(
+            "a base16 palette has 16 colors".to_owned(),
+        ));
+    }
+    let slot_name = |slot: usize| format!("base{slot:02X}");
+    let oklchs: Vec<[f32; 3]> = palette.colors.iter().map(|&color| oklch(color)).collect();
+    let kind = if oklchs[0x00][0] > oklchs[0x05][0] {
+        Kind::Light
+    } else {
+        Kind::Dark
+    };
+
+    // the greys, from the background to the foreground
+    let mut ramp: Vec<usize> = (0x00..=0x07).collect();
+    ramp.sort_by(|&slot1, &slot2| {
+        let order = oklchs[slot1][0].total_cmp(&oklchs[slot2][0]);
+        match kind {
+            Kind::Dark => order,
+            Kind::Light => order.reverse(),
+        }
+    });
+
+    let accents: Vec<(usize, &str)> = BASE16_ACCENTS
+        .iter()
+        .copied()
+        .filter(|&(slot, _)| slot < slots)
+        .collect();
+    let clusters: Vec<Cluster> = accents
+        .iter()
+        .map(|&(slot, _)| Cluster {
+            color: palette.colors[slot],
+            oklch: oklchs[slot],
+            members: vec![palette.colors[slot]],
+            fg_uses: 1,
+            bg_uses: 0,
+        })
+        .collect();
+    let (mut hues, mut groups, factored) = factor_colors(&clusters);
+
+    // hues are named after the first accent sharing them, in the order they were factored
+    let mut taken = BTreeSet::new();
+    for (cluster, &(_, role)) in clusters.iter().zip(&accents) {
+        if cluster.oklch[1] < ACHROMATIC {
+            continue;
+        }
+        let hue = hues
+            .iter_mut()
+            .find(|hue| hue_distance(hue.hue, cluster.oklch[2]) <= HUE_TOLERANCE)
+            .unwrap();
+        if hue.name.is_empty() {
+            hue.name = unique_name(role, &mut taken);
+        }
+    }
+    let mut taken = BTreeSet::new();
+    for group in &mut groups {
+        group.name = unique_name("fg", &mut taken);
+    }
+
+    let mut references: Vec<String> = (0..slots).map(slot_name).collect();
+    for (idx, &(slot, _)) in accents.iter().enumerate() {
+        if let Some((group, hue)) = factored[idx] {
+            references[slot] = format!("{}.{}", groups[group].name, hues[hue].name);
+        }
+    }
+    let colors: Vec<(String, [f32; 3])> = (0..slots)
+        .filter(|&slot| !references[slot].contains('.'))
+        .map(|slot| (slot_name(slot), oklchs[slot]))
+        .collect();
+
+    let grey = |step: usize| format!("{:?}", references[ramp[step]]);
+    let accent = |slot: usize| format!("{:?}", references[slot]);
+    let tint = |slot: usize, amount: f32| {
+        format!(
+            "{{ color = {:?}, mix = [{:?}, {amount}] }}",
+            references[slot], references[ramp[0]]
+        )
+    };
+    // base24 palettes have separate bright accents
+    let bright = |slot: usize, base24_slot: usize| {
+        accent(if base24_slot < slots {
+            base24_slot
+        } else {
+            slot
+        })
+    };
+
+    let elements = [
+        ("ui", "fg", grey(5)),
+        ("ui", "fg_dim", grey(4)),
+        ("ui", "fg_very_dim", grey(3)),
+        ("ui", "bg", grey(0)),
+        ("ui", "bg_visual", grey(2)),
+        ("ui", "bg_highlight", grey(2)),
+        ("ui", "bg_highlight_dim", grey(1)),
+        ("ui", "bg_border", grey(2)),
+        ("ui", "bg_gutter", grey(1)),
+        ("ui", "bg_dim", grey(1)),
+        ("ui", "bg_selected", grey(1)),
+        ("ui", "float_bg", grey(1)),
+        ("ui", "float_fg", grey(5)),
+        ("ui", "float_border", grey(3)),
+        ("ui", "pmenu_bg", grey(1)),
+        ("ui", "pmenu_fg", grey(5)),
+        ("ui", "pmenu_fg_selected", grey(6)),
+        ("ui", "pmenu_bg_selected", grey(2)),
+        ("ui", "special", accent(0x0c)),
+        ("ui", "nontext", grey(3)),
+        ("syn", "string", accent(0x0b)),
+        ("syn", "number", accent(0x09)),
+        ("syn", "constant", accent(0x09)),
+        ("syn", "regex", accent(0x0c)),
+        ("syn", "preproc", accent(0x0a)),
+        ("syn", "type", accent(0x0a)),
+        ("syn", "variable", accent(0x08)),
+        ("syn", "identifier", accent(0x08)),
+        ("syn", "parameter", accent(0x08)),
+        ("syn", "function", accent(0x0d)),
+        ("syn", "constructor", accent(0x0a)),
+        ("syn", "statement", accent(0x0e)),
+        ("syn", "keyword", accent(0x0e)),
+        ("syn", "operator", grey(5)),
+        ("syn", "deprecated", accent(0x0f)),
+        ("syn", "comment", grey(3)),
+        ("syn", "special", accent(0x0c)),
+        ("syn", "punctuation", grey(5)),
+        ("diagnostics", "error", accent(0x08)),
+        ("diagnostics", "warning", accent(0x09)),
+        ("diagnostics", "info", accent(0x0d)),
+        ("diagnostics", "hint", accent(0x0c)),
+        ("diagnostics", "ok", accent(0x0b)),
+        ("vcs", "removed", accent(0x08)),
+        ("vcs", "added", accent(0x0b)),
+        ("vcs", "changed", accent(0x0e)),
+        ("diff", "removed", tint(0x08, 0.8)),
+        ("diff", "added", tint(0x0b, 0.8)),
+        ("diff", "changed", tint(0x0e, 0.8)),
+        ("diff", "text", tint(0x0e, 0.6)),
+        ("terminal", "black", grey(0)),
+        ("terminal", "red", accent(0x08)),
+        ("terminal", "green", accent(0x0b)),
+        ("terminal", "yellow", accent(0x0a)),
+        ("terminal", "blue", accent(0x0d)),
+        ("terminal", "magenta", accent(0x0e)),
+        ("terminal", "cyan", accent(0x0c)),
+        ("terminal", "white", grey(5)),
+        ("terminal", "bright_black", grey(3)),
+        ("terminal", "bright_red", bright(0x08, 0x12)),
+        ("terminal", "bright_green", bright(0x0b, 0x14)),
+        ("terminal", "bright_yellow", bright(0x0a, 0x13)),
+        ("terminal", "bright_blue", bright(0x0d, 0x16)),
+        ("terminal", "bright_magenta", bright(0x0e, 0x17)),
+        ("terminal", "bright_cyan", bright(0x0c, 0x15)),
+        ("terminal", "bright_white", grey(7)),
+    ];
+    let slot_names: Vec<String> = (0..slots).map(slot_name).collect();
+    let mut themes: BTreeMap<&str, BTreeMap<&str, String>> = BTreeMap::new();
+    for (namespace, element, value) in &elements {
+        themes
+            .entry(namespace)
+            .or_default()
+            .insert(element, value.clone());
+    }
+    for (slot, name) in slot_names.iter().enumerate() {
+        themes
+            .entry("palette")
+            .or_default()
+            .insert(name, accent(slot));
+    }
+
+    let used_hues: BTreeSet<usize> = (0..hues.len()).collect();
+    let configuration = write_configuration(
+        "Imported by hi.nvim.rs from a base16 palette. Colors are named after their\n\
+         slots, and accents are factored into hues and groups where they line up.",
+        name,
+        kind,
+        &hues,
+        &used_hues,
+        &groups,
+        &colors,
+        &themes,
+        &[],
+    )
+    .map_err(|_| Error::CompilationFailed)?;
+
+    // compile the configuration, and compare it with the palette
+    let colorscheme = crate::parse(&configuration)?;
+    let theme = colorscheme.theme(kind);
+    let mut differences = Vec::new();
+    let mut converted_colors = HashSet::new();
+    for (name, &original) in slot_names.iter().zip(&palette.colors) {
+        let Some(converted) = theme.get_color_by_name(&format!("palette.{name}")) else {
+            continue;
+        };
+        converted_colors.insert(converted);
+        if original != converted {
+            differences.push(Difference {
+                group: name.clone(),
+                attribute: "color",
+                original,
+                converted,
+                delta_eok: original.delta_eok(converted),
+            });
+        }
+    }
+    differences.sort_by(|difference1, difference2| {
+        difference2.delta_eok.total_cmp(&difference1.delta_eok)
+    });
+
+    Ok(Conversion {
+        configuration,
+        original_colors: palette.colors.iter().collect::<HashSet<_>>().len(),
+        colors: converted_colors.len(),
+        differences,
+        warnings: palette.warnings.clone(),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_configuration(
+    header: &str,
+    name: &str,
+    kind: Kind,
+    hues: &[Hue],
+    used_hues: &BTreeSet<usize>,
+    groups: &[Group],
+    colors: &[(String, [f32; 3])],
+    themes: &BTreeMap<&str, BTreeMap<&str, String>>,
+    highlights: &[(&String, String)],
+) -> Result<String, std::fmt::Error> {
+    let mut config = String::with_capacity(16_384);
+
+    for line in header.lines() {
+        writeln!(config, "# {line}")?;
+    }
+    writeln!(config)?;
+    writeln!(config, "name = {name:?}")?;
+    writeln!(config, "kind = \"{kind}\"")?;
+
+    writeln!(config)?;
+    writeln!(config, "[hues]")?;
+    for &idx in used_hues {
+        writeln!(config, "{} = {:.2}", hues[idx].name, hues[idx].hue)?;
+    }
+
+    writeln!(config)?;
+    writeln!(config, "[groups]")?;
+    for group in groups {
+        writeln!(config)?;
+        writeln!(config, "[groups.{}]", group.name)?;
+        writeln!(config, "lightness = {:.4}", group.lightness)?;
+        writeln!(config, "chroma = {:.4}", group.chroma)?;
+    }
+
+    writeln!(config)?;
+    writeln!(config, "[colors]")?;
+    for (name, [l, c, h]) in colors {
+        writeln!(config, "{name} = [ {l:.4}, {c:.4}, {h:.2} ]")?;
+    }
+
+    for (namespace, elements) in themes {
+        writeln!(config)?;
+        writeln!(config, "[themes.{namespace}]")?;
+        for (element, value) in elements {
+            writeln!(config, "{element} = {value}")?;
+        }
+    }
+
+    writeln!(config)?;
+    writeln!(config, "[inverse]")?;
+
+    writeln!(config)?;
+    writeln!(config, "[highlights]")?;
+    for (group, highlight) in highlights {
+        writeln!(config, "{} = {highlight}", toml_key(group))?;
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        convert, convert_base16, parse_base16, parse_tmtheme, parse_vim, parse_vscode,
+        MERGE_DELTA_EOK,
+    };
+    use crate::{CompileOptions, Kind, Rgb8};
+
+    #[test]
+    fn notations_agree() {
+        let vim = parse_vim("hi Comment guifg=#808080 guibg=NONE gui=italic,bold");
+        let lua = parse_vim(
+            r##"vim.api.nvim_set_hl(0, "Comment", { fg = "#808080", italic = true, bold = true })"##,
+        );
+        let dump = parse_vim("Comment        xxx gui=bold,italic guifg=#808080");
+
+        let comment = &vim.highlights["Comment"];
+        assert_eq!(
+            comment.fg,
+            Some(Rgb8 {
+                red: 128,
+                green: 128,
+                blue: 128
+            })
+        );
+        assert!(comment.styles.contains("italic") && comment.styles.contains("bold"));
+        assert_eq!(&lua.highlights["Comment"], comment);
+        assert_eq!(&dump.highlights["Comment"], comment);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let options = CompileOptions {
+            background: Some(Kind::Dark),
+            ..Default::default()
+        };
+        let vim = crate::compile_vim_with_options(&colorscheme, &options).unwrap();
+        let conversion = convert(&parse_vim(&vim), "roundtrip").unwrap();
+
+        // near-identical colors are merged, and nothing else is changed noticeably
+        assert!(conversion.colors <= conversion.original_colors);
+        assert!(conversion
+            .differences
+            .iter()
+            .all(|difference| difference.delta_eok < 2. * MERGE_DELTA_EOK));
+        crate::parse(&conversion.configuration).unwrap();
+    }
+
+    #[test]
+    fn base16() {
+        let palette = parse_base16(
+            r#"
+scheme: "Gruvbox dark, medium"
+base00: "282828" # ----
+base01: "3c3836"
+base02: "504945"
+base03: "665c54"
+base04: "bdae93"
+base05: "d5c4a1"
+base06: "ebdbb2"
+base07: "fbf1c7" # ++++
+base08: "fb4934"
+base09: "fe8019"
+base0A: "fabd2f"
+base0B: "b8bb26"
+base0C: "8ec07c"
+base0D: "83a598"
+base0E: "d3869b"
+base0F: "d65d0e"
+"#,
+        )
+        .unwrap();
+        assert_eq!(palette.name.as_deref(), Some("Gruvbox dark, medium"));
+        assert_eq!(palette.colors.len(), 16);
+
+        let conversion = convert_base16(&palette, "gruvbox").unwrap();
+        assert!(conversion
+            .differences
+            .iter()
+            .all(|difference| difference.delta_eok < MERGE_DELTA_EOK));
+
+        // the configuration is complete, and the palette's background is the dark background
+        let colorscheme = crate::parse(&conversion.configuration).unwrap();
+        assert_eq!(
+            colorscheme.dark_theme.get_color_by_name("ui.bg"),
+            Some(palette.colors[0])
+        );
+        assert!(parse_base16("base00: \"282828\"").is_err());
+    }
+
+    #[test]
+    fn textmate_roundtrip() {
+        let colorscheme = crate::parse(include_str!("../colorschemes/highlow.toml")).unwrap();
+        let vscode = crate::compile_vscode(&colorscheme, Kind::Dark).unwrap();
+        let tmtheme = crate::compile_tmtheme(&colorscheme, Kind::Dark).unwrap();
+
+        for imported in [
+            parse_vscode(&vscode, &|_| unreachable!()).unwrap(),
+            parse_tmtheme(&tmtheme).unwrap(),
+        ] {
+            assert!(imported.highlights.contains_key("Comment"));
+            let conversion = convert(&imported, "roundtrip").unwrap();
+            assert!(conversion
+                .differences
+                .iter()
+                .all(|difference| difference.delta_eok < 2. * MERGE_DELTA_EOK));
+        }
+    }
+
+    #[test]
+    fn vscode_include() {
+        let theme = r##"{
+            // comments and trailing commas are allowed
+            "include": "./base.json",
+            "tokenColors": [{ "scope": "comment", "settings": { "foreground": "#808080" } },],
+        }"##;
+        let imported = parse_vscode(theme, &|path| {
+            assert_eq!(path, std::path::Path::new("./base.json"));
+            Ok(r##"{ "colors": { "editor.background": "#000000", "editor.foreground": "#ffffff80" } }"##.to_owned())
+        })
+        .unwrap();
+
+        let normal = &imported.highlights["Normal"];
+        assert_eq!(
+            normal.bg,
+            Some(Rgb8 {
+                red: 0,
+                green: 0,
+                blue: 0
+            })
+        );
+        // translucent colors are blended onto the background
+        assert_eq!(
+            normal.fg,
+            Some(Rgb8 {
+                red: 128,
+                green: 128,
+                blue: 128
+            })
+        );
+        assert_eq!(
+            imported.highlights["Comment"].fg,
+            Some(Rgb8 {
+                red: 128,
+                green: 128,
+                blue: 128
+            })
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Parsing of base16 and base24 palettes for importing.
+//!
+//! Both the original scheme format (`scheme: "Name"`, `base00: "181818"`) and the newer format
+//! with a nested `palette` mapping (`name: "Name"`, `base00: "#181818"`) are understood. The
+//! files are flat enough that they are read line by line, rather than as general YAML.
+
+use std::collections::BTreeMap;
+
+use crate::colorscheme::Rgb8;
+use crate::error::Error;
+use crate::import::Base16;
+
+/// Strip a trailing comment and the quotes of a scalar value.
+fn scalar(value: &str) -> &str {
+    let value = value.trim();
+    match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let value = &value[1..];
+            &value[..value.find(quote).unwrap_or(value.len())]
+        }
+        _ => value.split(" #").next().unwrap_or_default().trim(),
+    }
+}
+
+/// Parse a base16 or base24 palette. Fails if any of `base00` to `base0F` is missing or
+/// invalid.
+pub fn parse(source: &str) -> Result<Base16, Error> {
+    let mut name = None;
+    let mut slots = BTreeMap::new();
+    let mut warnings = Vec::new();
+
+    for line in source.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = scalar(value);
+
+        match key.trim() {
+            "scheme" | "name" if name.is_none() && !value.is_empty() => {
+                name = Some(value.to_owned());
+            }
+            key if key.len() == 6 && key.starts_with("base") => {
+                let Ok(slot) = u8::from_str_radix(&key[4..], 16) else {
+                    continue;
+                };
+                match value.parse::<Rgb8>() {
+                    Ok(color) => {
+                        slots.insert(slot, color);
+                    }
+                    Err(_) => warnings.push(format!("could not parse color of {key}: {value}")),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut colors = Vec::with_capacity(24);
+    for slot in 0..16 {
+        let color = slots
+            .get(&slot)
+            .ok_or_else(|| Error::ImportFailed(format!("the palette has no base{slot:02X}")))?;
+        colors.push(*color);
+    }
+    // base24 extends the palette with `base10` to `base17`, which must all be given
+    if (16..24).all(|slot| slots.contains_key(&slot)) {
+        colors.extend((16..24).map(|slot| slots[&slot]));
+    } else if (16..24).any(|slot| slots.contains_key(&slot)) {
+        warnings.push(
+            "the palette has some but not all base24 colors; importing it as base16".to_owned(),
+        );
+    }
+
+    Ok(Base16 {
+        name,
+        colors,
+        warnings,
+    })
+}
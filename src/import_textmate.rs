@@ -0,0 +1,589 @@
+//! Parsing of VS Code color themes and TextMate (`.tmTheme`) color schemes for importing.
+//!
+//! Both consist of TextMate scope rules (a VS Code theme's `tokenColors`). The rules are mapped
+//! back onto highlight groups through the same scope mapping the TextMate and VS Code targets
+//! compile with: the rules that apply to `keyword.control` give the colors of
+//! `@keyword.conditional`, and through Neovim's default links, of `Statement`. The workbench
+//! colors of a VS Code theme are mapped back onto theme elements directly.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::captures::{DEFAULT_LINKS, TEXTMATE_SCOPES};
+use crate::colorscheme::Rgb8;
+use crate::compiler_vscode::{TERMINAL_COLORS, WORKBENCH_COLORS};
+use crate::error::Error;
+use crate::import::{Imported, ImportedColor, ImportedHighlight};
+
+/// The global settings of a TextMate color scheme, with the highlight groups and attributes they
+/// are compiled from.
+const GLOBALS: &[(&str, &str, &str)] = &[
+    ("foreground", "Normal", "fg"),
+    ("background", "Normal", "bg"),
+    ("caret", "Cursor", "bg"),
+    ("selection", "Visual", "bg"),
+    ("lineHighlight", "CursorLine", "bg"),
+    ("gutter", "SignColumn", "bg"),
+    ("gutterForeground", "LineNr", "fg"),
+];
+
+const BLACK: Rgb8 = Rgb8 {
+    red: 0,
+    green: 0,
+    blue: 0,
+};
+
+/// How deep themes may include other themes, in case the includes form a cycle.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A TextMate scope rule.
+struct Rule {
+    /// The scope selectors the rule applies to, as written.
+    scope: String,
+    selectors: Vec<String>,
+    fg: Option<Rgb8>,
+    bg: Option<Rgb8>,
+    /// The styles set by the rule, if it sets a font style.
+    styles: Option<BTreeSet<&'static str>>,
+}
+
+#[derive(Default)]
+struct Theme {
+    name: Option<String>,
+    /// Workbench colors, in VS Code notation.
+    colors: BTreeMap<String, String>,
+    /// Scope rules in TextMate notation, in order of precedence (later rules win).
+    rules: Vec<Value>,
+    semantic_token_colors: Vec<String>,
+}
+
+/// Strip the comments and trailing commas VS Code allows in its JSON files.
+fn strip_jsonc(source: &str) -> String {
+    let mut json = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                json.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        json.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            ('}' | ']', _) => {
+                let trimmed = json.trim_end().len();
+                json.truncate(trimmed);
+                if json.ends_with(',') {
+                    json.pop();
+                }
+                json.push(c);
+            }
+            _ => json.push(c),
+        }
+    }
+    json
+}
+
+/// Replace the XML entities of the text of a plist element.
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+enum Token<'s> {
+    Open(&'s str),
+    Close(&'s str),
+    Empty(&'s str),
+    Text(&'s str),
+}
+
+/// The name of an XML tag, without its attributes.
+fn tag_name(tag: &str) -> &str {
+    tag.split_whitespace().next().unwrap_or_default()
+}
+
+/// The tags and texts of an XML document, skipping declarations and comments.
+fn xml_tokens(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find('<') {
+        if !rest[..start].trim().is_empty() {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        rest = &rest[start..];
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+        if let Some(tag) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(tag_name(tag)));
+        } else if let Some(tag) = tag.strip_suffix('/') {
+            tokens.push(Token::Empty(tag_name(tag)));
+        } else {
+            tokens.push(Token::Open(tag_name(tag)));
+        }
+    }
+    tokens
+}
+
+/// Parse the plist value starting at `tokens[*pos]`.
+fn plist_value(tokens: &[Token], pos: &mut usize) -> Result<Value, Error> {
+    let invalid = || Error::ImportFailed("invalid property list".to_owned());
+    let token = tokens.get(*pos).ok_or_else(invalid)?;
+    *pos += 1;
+
+    match *token {
+        Token::Empty("true") => Ok(Value::Bool(true)),
+        Token::Empty("false") => Ok(Value::Bool(false)),
+        Token::Empty("dict") => Ok(Value::Object(Default::default())),
+        Token::Empty("array") => Ok(Value::Array(Vec::new())),
+        Token::Empty(_) => Ok(Value::String(String::new())),
+        Token::Open("plist") => {
+            let value = plist_value(tokens, pos)?;
+            *pos += 1;
+            Ok(value)
+        }
+        Token::Open("dict") => {
+            let mut dict = serde_json::Map::new();
+            loop {
+                match tokens.get(*pos).ok_or_else(invalid)? {
+                    Token::Close("dict") => {
+                        *pos += 1;
+                        return Ok(Value::Object(dict));
+                    }
+                    Token::Open("key") => {
+                        *pos += 1;
+                        let key = match tokens.get(*pos) {
+                            Some(Token::Text(key)) => {
+                                *pos += 2;
+                                unescape_xml(key.trim())
+                            }
+                            _ => {
+                                *pos += 1;
+                                String::new()
+                            }
+                        };
+                        dict.insert(key, plist_value(tokens, pos)?);
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+        }
+        Token::Open("array") => {
+            let mut array = Vec::new();
+            loop {
+                if let Token::Close("array") = tokens.get(*pos).ok_or_else(invalid)? {
+                    *pos += 1;
+                    return Ok(Value::Array(array));
+                }
+                array.push(plist_value(tokens, pos)?);
+            }
+        }
+        Token::Open(_) => match tokens.get(*pos) {
+            Some(Token::Text(text)) => {
+                *pos += 2;
+                Ok(Value::String(unescape_xml(text.trim())))
+            }
+            _ => {
+                *pos += 1;
+                Ok(Value::String(String::new()))
+            }
+        },
+        Token::Close(_) | Token::Text(_) => Err(invalid()),
+    }
+}
+
+/// Parse a color in VS Code notation (`#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`). Translucent
+/// colors are blended onto `background`.
+fn color(value: &str, background: Rgb8) -> Option<Rgb8> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex: String = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 | 8 => hex.to_owned(),
+        _ => return None,
+    };
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap();
+    let alpha = if hex.len() == 8 { channel(6) } else { 255 };
+    let blend = |channel: u8, background: u8| {
+        let alpha = f32::from(alpha) / 255.;
+        (f32::from(channel) * alpha + f32::from(background) * (1. - alpha)).round() as u8
+    };
+    Some(Rgb8 {
+        red: blend(channel(0), background.red),
+        green: blend(channel(2), background.green),
+        blue: blend(channel(4), background.blue),
+    })
+}
+
+/// The highlight group the highlights of `capture` end up in through Neovim's fallbacks, which is
+/// the first group the default highlights define. Also returns the number of fallbacks taken.
+fn fallback_group(capture: &str) -> (&str, usize) {
+    let defined = |group: &str| {
+        crate::DEFAULT_HIGHLIGHTS
+            .iter()
+            .any(|(other, highlight)| *other == group && highlight.link.is_none())
+    };
+    let mut group = capture;
+    let mut steps = 0;
+    while !defined(group) {
+        let next = DEFAULT_LINKS
+            .iter()
+            .find(|(from, _)| *from == group)
+            .map(|(_, to)| *to)
+            .or_else(|| group.rsplit_once('.').map(|(parent, _)| parent));
+        match next {
+            Some(next) => {
+                group = next;
+                steps += 1;
+            }
+            None => return (capture, 0),
+        }
+    }
+    (group, steps)
+}
+
+/// Whether a scope selector applies to `scope`. Only plain selectors are understood, not
+/// descendant selectors (e.g., `meta.tag string`) or exclusions.
+fn applies(selector: &str, scope: &str) -> bool {
+    scope == selector
+        || scope
+            .strip_prefix(selector)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+impl Theme {
+    /// Load a VS Code color theme from `path` (relative to the including theme), loading the
+    /// themes it includes first.
+    fn load_vscode(
+        &mut self,
+        source: &str,
+        path: &Path,
+        include: &dyn Fn(&Path) -> Result<String, Error>,
+        depth: usize,
+    ) -> Result<(), Error> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(Error::ImportFailed(format!(
+                "too many nested includes at {}",
+                path.display()
+            )));
+        }
+        let theme: Value = serde_json::from_str(&strip_jsonc(source)).map_err(|err| {
+            if path.as_os_str().is_empty() {
+                Error::ImportFailed(err.to_string())
+            } else {
+                Error::ImportFailed(format!("{}: {err}", path.display()))
+            }
+        })?;
+        let relative =
+            |file: &str| -> PathBuf { path.parent().unwrap_or_else(|| Path::new("")).join(file) };
+
+        if let Some(file) = theme["include"].as_str() {
+            let file = relative(file);
+            self.load_vscode(&include(&file)?, &file, include, depth + 1)?;
+        }
+        if let Some(name) = theme["name"].as_str() {
+            self.name = Some(name.to_owned());
+        }
+        if let Some(colors) = theme["colors"].as_object() {
+            for (key, value) in colors {
+                if let Some(value) = value.as_str() {
+                    self.colors.insert(key.clone(), value.to_owned());
+                }
+            }
+        }
+        match &theme["tokenColors"] {
+            Value::Array(rules) => self.rules.extend(rules.iter().cloned()),
+            Value::String(file) => {
+                let file = relative(file);
+                self.load_tmtheme(&include(&file)?)?;
+            }
+            _ => {}
+        }
+        if let Some(tokens) = theme["semanticTokenColors"].as_object() {
+            self.semantic_token_colors.extend(tokens.keys().cloned());
+        }
+
+        Ok(())
+    }
+
+    fn load_tmtheme(&mut self, source: &str) -> Result<(), Error> {
+        let tokens = xml_tokens(source);
+        let theme = plist_value(&tokens, &mut 0)?;
+        if let Some(name) = theme["name"].as_str() {
+            self.name.get_or_insert_with(|| name.to_owned());
+        }
+        if let Some(rules) = theme["settings"].as_array() {
+            self.rules.extend(rules.iter().cloned());
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Imported {
+        let mut warnings = Vec::new();
+        let mut highlights: BTreeMap<String, ImportedHighlight> = BTreeMap::new();
+        let mut colors = Vec::new();
+
+        let global = |key: &str| {
+            self.rules
+                .iter()
+                .rev()
+                .filter(|rule| rule.get("scope").is_none())
+                .find_map(|rule| rule["settings"][key].as_str())
+        };
+        let background = self
+            .colors
+            .get("editor.background")
+            .map(String::as_str)
+            .or_else(|| global("background"))
+            .and_then(|value| color(value, BLACK))
+            .unwrap_or(BLACK);
+        let parse_color = |value: &str| color(value, background);
+
+        // the global settings of a TextMate color scheme
+        for (key, group, attribute) in GLOBALS {
+            let Some(color) = global(key).and_then(parse_color) else {
+                continue;
+            };
+            let highlight = highlights.entry(group.to_string()).or_default();
+            match *attribute {
+                "fg" => highlight.fg = Some(color),
+                _ => highlight.bg = Some(color),
+            }
+        }
+
+        // the workbench colors of a VS Code theme
+        let mut unmapped = Vec::new();
+        let mut terminal = [None; 16];
+        for (key, value) in &self.colors {
+            let Some(color) = parse_color(value) else {
+                warnings.push(format!("could not parse workbench color {key}: {value}"));
+                continue;
+            };
+            if let Some(slot) = TERMINAL_COLORS.iter().position(|other| other == key) {
+                terminal[slot] = Some(color);
+                continue;
+            }
+            match key.as_str() {
+                "editor.foreground" => {
+                    highlights.entry("Normal".to_owned()).or_default().fg = Some(color)
+                }
+                "editor.background" => {
+                    highlights.entry("Normal".to_owned()).or_default().bg = Some(color)
+                }
+                _ => {}
+            }
+            let elements: Vec<&str> = WORKBENCH_COLORS
+                .iter()
+                .filter(|(other, _)| other == key)
+                .map(|(_, element)| *element)
+                .collect();
+            if elements.is_empty() {
+                unmapped.push(key.as_str());
+            }
+            for element in elements {
+                colors.push(ImportedColor {
+                    source: key.clone(),
+                    element: element.to_owned(),
+                    color,
+                });
+            }
+        }
+        let terminal = if terminal.iter().all(Option::is_some) {
+            Some(terminal.map(Option::unwrap))
+        } else {
+            if terminal.iter().any(Option::is_some) {
+                unmapped.extend(
+                    TERMINAL_COLORS
+                        .iter()
+                        .copied()
+                        .filter(|key| self.colors.contains_key(*key)),
+                );
+            }
+            None
+        };
+        if !unmapped.is_empty() {
+            warnings.push(format!(
+                "could not map {} workbench colors: {}",
+                unmapped.len(),
+                unmapped.join(", ")
+            ));
+        }
+
+        // the scope rules
+        let rules: Vec<Rule> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                let scope = match &rule["scope"] {
+                    Value::String(scope) => scope.clone(),
+                    Value::Array(scopes) => scopes
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    _ => return None,
+                };
+                let settings = &rule["settings"];
+                let mut parsed = Rule {
+                    selectors: scope
+                        .split(',')
+                        .map(|selector| selector.trim().to_owned())
+                        .collect(),
+                    scope,
+                    fg: settings["foreground"].as_str().and_then(parse_color),
+                    bg: settings["background"].as_str().and_then(parse_color),
+                    styles: settings["fontStyle"].as_str().map(|styles| {
+                        styles
+                            .split_whitespace()
+                            .filter_map(|style| match style {
+                                "bold" => Some("bold"),
+                                "italic" => Some("italic"),
+                                "underline" => Some("underline"),
+                                "strikethrough" => Some("strikethrough"),
+                                _ => None,
+                            })
+                            .collect()
+                    }),
+                };
+                parsed.selectors.retain(|selector| !selector.is_empty());
+                Some(parsed)
+            })
+            .collect();
+
+        let mut mapped_rules = BTreeSet::new();
+        let mut scope_highlights = Vec::new();
+        for (scope, capture) in TEXTMATE_SCOPES {
+            // the rules cascade from the least to the most specific, later rules winning ties
+            let mut applicable: Vec<(usize, usize)> = rules
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, rule)| {
+                    let specificity = rule
+                        .selectors
+                        .iter()
+                        .filter(|selector| applies(selector, scope))
+                        .map(|selector| selector.split('.').count())
+                        .max()?;
+                    Some((specificity, idx))
+                })
+                .collect();
+            if applicable.is_empty() {
+                continue;
+            }
+            applicable.sort();
+
+            let mut highlight = ImportedHighlight::default();
+            for &(_, idx) in &applicable {
+                let rule = &rules[idx];
+                mapped_rules.insert(idx);
+                highlight.fg = rule.fg.or(highlight.fg);
+                highlight.bg = rule.bg.or(highlight.bg);
+                if let Some(styles) = &rule.styles {
+                    highlight.styles = styles.clone();
+                }
+            }
+            let (group, steps) = fallback_group(capture);
+            scope_highlights.push((steps, *capture, group, highlight));
+        }
+
+        // general groups take the colors of the scopes linking to them most directly, and captures
+        // differing from their group get highlights of their own
+        scope_highlights.sort_by_key(|(steps, ..)| *steps);
+        for (_, capture, group, highlight) in scope_highlights {
+            let group_highlight = highlights
+                .entry(group.to_owned())
+                .or_insert_with(|| highlight.clone());
+            if *group_highlight != highlight {
+                highlights.entry(capture.to_owned()).or_insert(highlight);
+            }
+        }
+
+        let unmapped: Vec<&str> = rules
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !mapped_rules.contains(idx))
+            .map(|(_, rule)| rule.scope.as_str())
+            .collect();
+        if !unmapped.is_empty() {
+            warnings.push(format!(
+                "could not map {} token color rules: {}",
+                unmapped.len(),
+                unmapped.join("; ")
+            ));
+        }
+        if !self.semantic_token_colors.is_empty() {
+            warnings.push(format!(
+                "semantic token colors are not imported: {}",
+                self.semantic_token_colors.join(", ")
+            ));
+        }
+
+        Imported {
+            name: self.name,
+            highlights,
+            colors,
+            terminal,
+            warnings,
+        }
+    }
+}
+
+/// Parse a VS Code color theme. `include` reads the files the theme refers to (through `include`,
+/// or `tokenColors` given as the path of a TextMate color scheme), by their path relative to the
+/// theme.
+pub fn parse_vscode(
+    source: &str,
+    include: &dyn Fn(&Path) -> Result<String, Error>,
+) -> Result<Imported, Error> {
+    let mut theme = Theme::default();
+    theme.load_vscode(source, Path::new(""), include, 0)?;
+    Ok(theme.finish())
+}
+
+/// Parse a TextMate (`.tmTheme`) color scheme.
+pub fn parse_tmtheme(source: &str) -> Result<Imported, Error> {
+    let mut theme = Theme::default();
+    theme.load_tmtheme(source)?;
+    Ok(theme.finish())
+}
@@ -0,0 +1,499 @@
+//! Parsing of Vim and Neovim color schemes for importing.
+//!
+//! Three notations are understood, and may be mixed: Vim script `highlight` commands, Lua
+//! `nvim_set_hl` calls (or calls of an alias taking the same arguments, such as
+//! `hl(0, "Normal", { fg = "#c0c0c0" })`), and the output of the `:highlight` command. Only
+//! literal colors are understood. Colors the color scheme computes (e.g., through variables or
+//! `execute`) are reported as warnings.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::colorscheme::Rgb8;
+use crate::import::{Imported, ImportedHighlight, STYLES};
+
+/// A color, which may refer to the colors of the `Normal` group (as Vim's `fg` and `bg` do).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Color(Rgb8),
+    NormalFg,
+    NormalBg,
+}
+
+#[derive(Debug, Default)]
+struct Highlight {
+    fg: Option<Value>,
+    bg: Option<Value>,
+    sp: Option<Value>,
+    link: Option<String>,
+    styles: BTreeSet<&'static str>,
+}
+
+#[derive(Default)]
+struct Parser {
+    name: Option<String>,
+    highlights: BTreeMap<String, Highlight>,
+    terminal: [Option<Rgb8>; 16],
+    /// Lua variables and table fields assigned string literals, by path (e.g., `colors.red`).
+    variables: BTreeMap<String, String>,
+    warnings: Vec<String>,
+}
+
+/// The style named `name`, as named in the configuration.
+fn style(name: &str) -> Option<&'static str> {
+    match name.to_ascii_lowercase().as_str() {
+        "inverse" | "standout" => Some("reverse"),
+        name => STYLES.iter().copied().find(|style| *style == name),
+    }
+}
+
+/// Strip the quotes of a string literal.
+fn unquote(value: &str) -> Option<&str> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+/// The string literal assigned on a line after `from` (e.g., `"highlow"` in
+/// `let g:colors_name = "highlow"`).
+fn assigned_string(line: &str, from: usize) -> Option<&str> {
+    let (_, value) = line[from..].split_once('=')?;
+    unquote(value.trim_start_matches('=').trim())
+}
+
+/// A Lua variable or table field path, with index expressions written as fields (e.g.,
+/// `colors.red` for `colors["red"]`).
+fn lua_path(expression: &str) -> String {
+    expression
+        .replace("[\"", ".")
+        .replace("['", ".")
+        .replace("\"]", "")
+        .replace("']", "")
+}
+
+/// The index of the brace closing the table opened at the start of `source`.
+fn table_end(source: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (idx, c) in source.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split the fields of a Lua table on top-level commas.
+fn table_fields(table: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (idx, c) in table.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{' | '(' | '[') => depth += 1,
+            (None, '}' | ')' | ']') => depth -= 1,
+            (None, ',' | ';') if depth == 0 => {
+                fields.push(&table[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&table[start..]);
+    fields
+        .into_iter()
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .collect()
+}
+
+impl Parser {
+    /// Parse a color value. Returns `None` for `NONE`, and for colors that cannot be parsed, which
+    /// are warned about.
+    fn value(&mut self, group: &str, value: &str) -> Option<Value> {
+        let value = unquote(value).unwrap_or(value);
+        match value.to_ascii_lowercase().as_str() {
+            "" | "none" => None,
+            "fg" | "foreground" => Some(Value::NormalFg),
+            "bg" | "background" => Some(Value::NormalBg),
+            _ => match value.parse::<Rgb8>() {
+                Ok(color) => Some(Value::Color(color)),
+                Err(_) => {
+                    self.warnings
+                        .push(format!("{group}: unsupported color `{value}` was ignored"));
+                    None
+                }
+            },
+        }
+    }
+
+    /// Parse a Lua color value, which can also be an integer (e.g., `0xc0c0c0`).
+    fn lua_value(&mut self, group: &str, value: &str) -> Option<Value> {
+        let integer = match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => value.parse::<u32>().ok(),
+        };
+        match integer {
+            Some(integer) => Some(Value::Color(Rgb8 {
+                red: (integer >> 16) as u8,
+                green: (integer >> 8) as u8,
+                blue: integer as u8,
+            })),
+            None if unquote(value).is_some() => self.value(group, value),
+            None if self.variables.contains_key(&lua_path(value)) => {
+                let value = self.variables[&lua_path(value)].clone();
+                self.value(group, &value)
+            }
+            None => {
+                self.warnings
+                    .push(format!("{group}: unsupported color `{value}` was ignored"));
+                None
+            }
+        }
+    }
+
+    fn link(&mut self, group: &str, link: &str) {
+        let highlight = self.highlights.entry(group.to_owned()).or_default();
+        *highlight = Highlight::default();
+        if !link.eq_ignore_ascii_case("NONE") {
+            highlight.link = Some(link.to_owned());
+        }
+    }
+
+    /// Parse the `key=value` attributes of a `highlight` command or `:highlight` output line.
+    /// Attributes that are not given are left as they are, as Vim does.
+    fn attributes<'t>(&mut self, group: &str, tokens: impl Iterator<Item = &'t str>) {
+        let mut fg = None;
+        let mut bg = None;
+        let mut sp = None;
+        let mut styles = None;
+        let mut clear = false;
+        for token in tokens {
+            if token.eq_ignore_ascii_case("NONE") || token == "cleared" {
+                clear = true;
+                continue;
+            }
+            let Some((key, value)) = token.split_once('=') else {
+                continue;
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "guifg" => fg = Some(self.value(group, value)),
+                "guibg" => bg = Some(self.value(group, value)),
+                "guisp" => sp = Some(self.value(group, value)),
+                "gui" => styles = Some(value.split(',').filter_map(style).collect()),
+                _ => {}
+            }
+        }
+
+        let highlight = self.highlights.entry(group.to_owned()).or_default();
+        if clear {
+            *highlight = Highlight::default();
+        }
+        if fg.is_some() || bg.is_some() || sp.is_some() || styles.is_some() {
+            highlight.link = None;
+        }
+        if let Some(fg) = fg {
+            highlight.fg = fg;
+        }
+        if let Some(bg) = bg {
+            highlight.bg = bg;
+        }
+        if let Some(sp) = sp {
+            highlight.sp = sp;
+        }
+        if let Some(styles) = styles {
+            highlight.styles = styles;
+        }
+    }
+
+    /// Parse the arguments of a Vim script `highlight` command.
+    fn highlight_command(&mut self, arguments: &str) {
+        let mut tokens = arguments
+            .split_whitespace()
+            .take_while(|token| !token.starts_with('"') && *token != "|")
+            .skip_while(|token| matches!(*token, "default" | "def"));
+
+        match tokens.next() {
+            Some("clear") => {
+                if let Some(group) = tokens.next() {
+                    self.highlights
+                        .insert(group.to_owned(), Highlight::default());
+                }
+            }
+            Some("link") => {
+                if let (Some(group), Some(link)) = (tokens.next(), tokens.next()) {
+                    self.link(group, link);
+                }
+            }
+            Some(group) => {
+                let group = group.to_owned();
+                self.attributes(&group, tokens);
+            }
+            None => {}
+        }
+    }
+
+    /// Parse a line of `:highlight` output (e.g., `Comment        xxx guifg=#7f7f7f`), given
+    /// without the `xxx` sample.
+    fn highlight_output<'t>(&mut self, group: &str, tokens: impl Iterator<Item = &'t str>) {
+        let tokens: Vec<&str> = tokens.collect();
+        match tokens.iter().position(|token| *token == "links") {
+            Some(idx) => {
+                if let Some(link) = tokens.get(idx + 2) {
+                    self.link(group, link);
+                }
+            }
+            None => self.attributes(group, tokens.into_iter()),
+        }
+    }
+
+    /// Parse the `g:terminal_color_<n>` variables a color scheme sets.
+    fn terminal_color(&mut self, line: &str) {
+        let Some(idx) = line.find("terminal_color_") else {
+            return;
+        };
+        let from = idx + "terminal_color_".len();
+        let digits: String = line[from..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        let (Ok(slot), Some(value)) = (digits.parse::<usize>(), assigned_string(line, from)) else {
+            return;
+        };
+        if slot < 16 {
+            match value.parse() {
+                Ok(color) => self.terminal[slot] = Some(color),
+                Err(_) => self.warnings.push(format!(
+                    "terminal_color_{slot}: unsupported color `{value}` was ignored"
+                )),
+            }
+        }
+    }
+
+    /// Collect the string literals assigned to Lua variables and (nested) table fields.
+    fn lua_variables(&mut self, source: &str) {
+        fn is_path(path: &str) -> bool {
+            !path.is_empty()
+                && path
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        }
+
+        fn fields(variables: &mut BTreeMap<String, String>, path: &str, table: &str) {
+            for field in table_fields(table) {
+                let Some((key, value)) = field.split_once('=') else {
+                    continue;
+                };
+                let key = lua_path(key.trim());
+                let key = key.trim_start_matches('.');
+                let value = value.trim();
+                if !is_path(key) {
+                    continue;
+                }
+                if let Some(value) = unquote(value) {
+                    variables.insert(format!("{path}.{key}"), value.to_owned());
+                } else if value.starts_with('{') {
+                    if let Some(end) = table_end(value) {
+                        fields(variables, &format!("{path}.{key}"), &value[1..end]);
+                    }
+                }
+            }
+        }
+
+        for (idx, _) in source.match_indices('=') {
+            let line_start = source[..idx].rfind('\n').map_or(0, |idx| idx + 1);
+            let target = source[line_start..idx].trim();
+            let target = target.strip_prefix("local ").unwrap_or(target).trim();
+            let value = source[idx + 1..].trim_start();
+            if !is_path(target) || value.starts_with('=') {
+                continue;
+            }
+
+            if let Some(string) = unquote(value) {
+                self.variables.insert(target.to_owned(), string.to_owned());
+            } else if value.starts_with('{') {
+                if let Some(end) = table_end(value) {
+                    fields(&mut self.variables, target, &value[1..end]);
+                }
+            }
+        }
+    }
+
+    /// Parse the Lua calls setting highlight groups in namespace 0.
+    fn lua_calls(&mut self, source: &str) {
+        for (idx, _) in source.match_indices('(') {
+            let line_start = source[..idx].rfind('\n').map_or(0, |idx| idx + 1);
+            if source[line_start..idx].trim_start().starts_with("--") {
+                continue;
+            }
+
+            let call = || {
+                let rest = source[idx + 1..].trim_start().strip_prefix('0')?;
+                let rest = rest.trim_start().strip_prefix(',')?.trim_start();
+                let group = unquote(rest)?;
+                let rest = rest[group.len() + 2..].trim_start().strip_prefix(',')?;
+                let rest = rest.trim_start();
+                if !rest.starts_with('{') {
+                    return None;
+                }
+                Some((group, &rest[1..table_end(rest)?]))
+            };
+            let Some((group, table)) = call() else {
+                continue;
+            };
+
+            let mut highlight = Highlight::default();
+            for field in table_fields(table) {
+                let Some((key, value)) = field.split_once('=') else {
+                    continue;
+                };
+                let key = key.trim();
+                let key = key
+                    .strip_prefix('[')
+                    .and_then(|key| key.strip_suffix(']'))
+                    .and_then(unquote)
+                    .unwrap_or(key);
+                let value = value.trim();
+                match key {
+                    "fg" | "foreground" => highlight.fg = self.lua_value(group, value),
+                    "bg" | "background" => highlight.bg = self.lua_value(group, value),
+                    "sp" | "special" => highlight.sp = self.lua_value(group, value),
+                    "link" => highlight.link = unquote(value).map(str::to_owned),
+                    key => {
+                        if let Some(style) = style(key).filter(|_| value == "true") {
+                            highlight.styles.insert(style);
+                        }
+                    }
+                }
+            }
+            if highlight.link.is_some() {
+                highlight = Highlight {
+                    link: highlight.link,
+                    ..Highlight::default()
+                };
+            }
+            self.highlights.insert(group.to_owned(), highlight);
+        }
+    }
+
+    fn parse(&mut self, source: &str) {
+        let mut executed = 0;
+        let mut output_group: Option<String> = None;
+
+        for raw_line in source.lines() {
+            let line = raw_line.trim_start().trim_start_matches(':').trim_start();
+            if line.starts_with('"') || line.starts_with("--") {
+                continue;
+            }
+
+            if let Some(idx) = line.find("colors_name") {
+                if let Some(name) = assigned_string(line, idx) {
+                    self.name = Some(name.to_owned());
+                }
+            }
+            self.terminal_color(line);
+
+            let (command, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match command {
+                "hi" | "hi!" | "highlight" | "highlight!" => {
+                    self.highlight_command(arguments);
+                    output_group = None;
+                }
+                "exe" | "exec" | "execute" if arguments.contains("hi") => executed += 1,
+                _ => {
+                    let mut tokens = line.split_whitespace();
+                    match (tokens.next(), tokens.next()) {
+                        (Some(group), Some("xxx")) => {
+                            self.highlight_output(group, tokens);
+                            output_group = Some(group.to_owned());
+                        }
+                        // `:highlight` output wraps long lines
+                        (Some(_), _) if raw_line.starts_with(char::is_whitespace) => {
+                            if let Some(group) = output_group.clone() {
+                                self.highlight_output(&group, line.split_whitespace());
+                            }
+                        }
+                        _ => output_group = None,
+                    }
+                }
+            }
+        }
+
+        self.lua_variables(source);
+        self.lua_calls(source);
+
+        if executed > 0 {
+            self.warnings.push(format!(
+                "{executed} `execute` statements setting highlights were not evaluated"
+            ));
+        }
+    }
+
+    fn finish(self) -> Imported {
+        let normal_color = |value: Option<Value>| match value {
+            Some(Value::Color(color)) => Some(color),
+            _ => None,
+        };
+        let normal = self.highlights.get("Normal");
+        let normal_fg = normal.and_then(|highlight| normal_color(highlight.fg));
+        let normal_bg = normal.and_then(|highlight| normal_color(highlight.bg));
+        let color = |value: Option<Value>| match value? {
+            Value::Color(color) => Some(color),
+            Value::NormalFg => normal_fg,
+            Value::NormalBg => normal_bg,
+        };
+
+        let highlights = self
+            .highlights
+            .into_iter()
+            .map(|(group, highlight)| {
+                let highlight = ImportedHighlight {
+                    fg: color(highlight.fg),
+                    bg: color(highlight.bg),
+                    sp: color(highlight.sp),
+                    link: highlight.link,
+                    styles: highlight.styles,
+                };
+                (group, highlight)
+            })
+            .collect();
+
+        let mut warnings = self.warnings;
+        let terminal = if self.terminal.iter().all(Option::is_some) {
+            Some(self.terminal.map(Option::unwrap))
+        } else {
+            if self.terminal.iter().any(Option::is_some) {
+                warnings.push("only some terminal colors are set; they were ignored".to_owned());
+            }
+            None
+        };
+
+        Imported {
+            name: self.name,
+            highlights,
+            colors: Vec::new(),
+            terminal,
+            warnings,
+        }
+    }
+}
+
+/// Parse a Vim script or Lua color scheme, or the output of `:highlight`.
+pub fn parse(source: &str) -> Imported {
+    let mut parser = Parser::default();
+    parser.parse(source);
+    parser.finish()
+}
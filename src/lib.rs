@@ -1,18 +1,66 @@
 //! A Neovim color scheme generator using a perceptual color space.
 
+mod captures;
 mod colorscheme;
+mod compiler_base16;
+mod compiler_common;
+mod compiler_css;
+mod compiler_desktop;
+mod compiler_emacs;
+mod compiler_helix;
+mod compiler_jetbrains;
+mod compiler_json;
+mod compiler_kakoune;
+mod compiler_micro;
 mod compiler_neovim;
+mod compiler_pygments;
+mod compiler_terminal;
+mod compiler_textmate;
+mod compiler_tools;
 mod compiler_vim;
+mod compiler_vscode;
+mod compiler_zed;
 mod configuration;
+pub mod contrast;
+pub mod cvd;
 mod de;
 mod default_highlights;
+pub mod distinguishability;
 mod error;
 mod gamut_map;
+pub mod import;
+mod import_base16;
+mod import_textmate;
+mod import_vim;
 mod modifiers;
+mod options;
+mod ser;
+pub mod terminal;
+pub mod terminal16;
 
 pub use colorscheme::{parse, Colorscheme, Rgb8, Theme};
-pub use compiler_neovim::compile as compile_neovim;
-pub use compiler_vim::compile as compile_vim;
+pub use compiler_base16::{compile_base16, compile_base24};
+pub use compiler_css::{compile as compile_css, Gamut as CssGamut};
+pub use compiler_desktop::{compile as compile_desktop, Component as DesktopComponent};
+pub use compiler_emacs::compile as compile_emacs;
+pub use compiler_helix::compile as compile_helix;
+pub use compiler_jetbrains::compile as compile_jetbrains;
+pub use compiler_json::compile as compile_json;
+pub use compiler_kakoune::compile as compile_kakoune;
+pub use compiler_micro::compile as compile_micro;
+pub use compiler_neovim::{
+    compile as compile_neovim, compile_lua as compile_neovim_lua,
+    compile_lua_with_options as compile_neovim_lua_with_options,
+    compile_with_options as compile_neovim_with_options,
+};
+pub use compiler_pygments::compile as compile_pygments;
+pub use compiler_terminal::{compile as compile_terminal, Emulator as TerminalEmulator};
+pub use compiler_textmate::{compile_sublime as compile_sublime_color_scheme, compile_tmtheme};
+pub use compiler_tools::{compile as compile_tool, Tool as CliTool};
+pub use compiler_vim::{compile as compile_vim, compile_with_options as compile_vim_with_options};
+pub use compiler_vscode::{compile as compile_vscode, compile_package as compile_vscode_package};
+pub use compiler_zed::compile as compile_zed;
 pub use configuration::{Highlight, Kind, NamespacedThemeElement};
 pub use default_highlights::DEFAULT_HIGHLIGHTS;
 pub use error::Error;
+pub use options::CompileOptions;
@@ -1,63 +1,850 @@
 //! A Neovim color scheme compiler using a perceptual color space.
 
-use clap::{Parser, ValueEnum};
-use std::{io::Read, path::PathBuf};
+use anyhow::Context;
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
 
-mod colorscheme;
-mod compiler_neovim;
-mod compiler_vim;
-mod configuration;
-mod de;
-pub mod default_highlights;
-mod error;
-mod gamut_map;
-mod modifiers;
-
-use default_highlights::DEFAULT_HIGHLIGHTS;
+use hi_nvim_rs::{
+    contrast, cvd, distinguishability, import, terminal16, CliTool, Colorscheme, CompileOptions,
+    CssGamut, DesktopComponent, Error, Kind, TerminalEmulator,
+};
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum Target {
     Neovim,
+    /// A standalone Neovim Lua color scheme, to be placed in `colors/<name>.lua`.
+    NeovimLua,
     Vim,
+    /// A Helix theme.
+    Helix,
+    /// A VS Code color theme. With `--output-dir`, a `package.json` is written alongside, so the
+    /// directory can be installed as an extension.
+    Vscode,
+    /// A Zed theme family, holding both the light and dark theme.
+    Zed,
+    /// A TextMate theme, as read by `bat` and `delta`.
+    Tmtheme,
+    /// A Sublime Text color scheme.
+    Sublime,
+    /// An Emacs theme, holding both the light and dark theme.
+    Emacs,
+    /// A Kakoune color scheme.
+    Kakoune,
+    /// A Micro color scheme.
+    Micro,
+    /// A JetBrains editor color scheme, for IntelliJ IDEA and other JetBrains IDEs.
+    Jetbrains,
+    /// A Python module with Pygments styles, holding a class for both the light and dark theme.
+    Pygments,
+    /// A kitty theme.
+    Kitty,
+    /// An Alacritty theme.
+    Alacritty,
+    /// A WezTerm color scheme.
+    Wezterm,
+    /// A foot theme.
+    Foot,
+    /// A Ghostty theme.
+    Ghostty,
+    /// A Windows Terminal color scheme.
+    WindowsTerminal,
+    /// X resources.
+    Xresources,
+    /// fzf colors, as a shell snippet extending `FZF_DEFAULT_OPTS`.
+    Fzf,
+    /// A `[delta]` section, to be included in `.gitconfig`.
+    Delta,
+    /// A lazygit theme, to be merged with lazygit's `config.yml`.
+    Lazygit,
+    /// A dircolors database, to be evaluated with `dircolors` to set `LS_COLORS`.
+    Dircolors,
+    /// A tmux status and pane theme, to be sourced in `tmux.conf`.
+    Tmux,
+    /// The fzf, delta, lazygit, dircolors and tmux themes at once. Requires `--output-dir`.
+    Tools,
+    /// A Waybar style sheet.
+    Waybar,
+    /// sway or i3 window colors, to be included in their configuration.
+    #[value(alias = "i3")]
+    Sway,
+    /// A rofi theme.
+    Rofi,
+    /// dunst urgency colors, to be included in `dunstrc`.
+    Dunst,
+    /// The Waybar, sway, rofi and dunst themes at once. Requires `--output-dir`.
+    Desktop,
+    /// A base16 palette, in tinted-theming's YAML scheme format.
+    Base16,
+    /// A base24 palette, in tinted-theming's YAML scheme format.
+    Base24,
+    /// The resolved theme element colors and highlight groups of both the light and dark theme,
+    /// as JSON for other programs to consume.
+    Json,
+    /// A CSS style sheet with custom properties for the theme elements and classes for the
+    /// highlight groups, following the `prefers-color-scheme` media query.
+    Css,
+}
+
+impl Target {
+    /// The terminal emulator this target compiles a theme for, if any.
+    fn emulator(self) -> Option<TerminalEmulator> {
+        match self {
+            Target::Kitty => Some(TerminalEmulator::Kitty),
+            Target::Alacritty => Some(TerminalEmulator::Alacritty),
+            Target::Wezterm => Some(TerminalEmulator::Wezterm),
+            Target::Foot => Some(TerminalEmulator::Foot),
+            Target::Ghostty => Some(TerminalEmulator::Ghostty),
+            Target::WindowsTerminal => Some(TerminalEmulator::WindowsTerminal),
+            Target::Xresources => Some(TerminalEmulator::Xresources),
+            _ => None,
+        }
+    }
+
+    /// The command-line tool this target compiles a theme for, if any.
+    fn tool(self) -> Option<CliTool> {
+        match self {
+            Target::Fzf => Some(CliTool::Fzf),
+            Target::Delta => Some(CliTool::Delta),
+            Target::Lazygit => Some(CliTool::Lazygit),
+            Target::Dircolors => Some(CliTool::Dircolors),
+            Target::Tmux => Some(CliTool::Tmux),
+            _ => None,
+        }
+    }
+
+    /// The desktop component this target compiles a theme for, if any.
+    fn component(self) -> Option<DesktopComponent> {
+        match self {
+            Target::Waybar => Some(DesktopComponent::Waybar),
+            Target::Sway => Some(DesktopComponent::Sway),
+            Target::Rofi => Some(DesktopComponent::Rofi),
+            Target::Dunst => Some(DesktopComponent::Dunst),
+            _ => None,
+        }
+    }
+
+    /// The targets this target compiles to, which is itself unless it is a bundle.
+    fn expand(self) -> Vec<Target> {
+        match self {
+            Target::Tools => vec![
+                Target::Fzf,
+                Target::Delta,
+                Target::Lazygit,
+                Target::Dircolors,
+                Target::Tmux,
+            ],
+            Target::Desktop => vec![Target::Waybar, Target::Sway, Target::Rofi, Target::Dunst],
+            target => vec![target],
+        }
+    }
+
+    /// Whether this target can map the theme onto the terminal's 16 ANSI colors.
+    fn uses_terminal16(self) -> bool {
+        matches!(self, Target::Neovim | Target::NeovimLua | Target::Vim)
+    }
+
+    /// Whether this target compiles the light and dark theme to separate files.
+    fn per_background(self) -> bool {
+        !matches!(
+            self,
+            Target::Neovim
+                | Target::NeovimLua
+                | Target::Vim
+                | Target::Zed
+                | Target::Emacs
+                | Target::Json
+                | Target::Css
+                | Target::Pygments
+        )
+    }
+
+    /// The name of a compiled file.
+    fn file_name(self, name: &str) -> String {
+        let extension = match self {
+            Target::Neovim | Target::Vim => "vim",
+            Target::NeovimLua => "lua",
+            Target::Kitty => "conf",
+            Target::Helix | Target::Alacritty | Target::Wezterm => "toml",
+            Target::Foot => "ini",
+            Target::Ghostty => return name.to_owned(),
+            Target::Emacs => return format!("{name}-theme.el"),
+            // Python modules cannot be imported by names containing dashes
+            Target::Pygments => return format!("{}.py", name.replace('-', "_")),
+            Target::Vscode | Target::Zed | Target::WindowsTerminal | Target::Json => "json",
+            Target::Tmtheme => "tmTheme",
+            Target::Sublime => "sublime-color-scheme",
+            Target::Xresources => "Xresources",
+            Target::Kakoune => "kak",
+            Target::Micro => "micro",
+            Target::Jetbrains => "icls",
+            Target::Base16 | Target::Base24 => "yaml",
+            Target::Css => "css",
+            Target::Fzf => "fzf.sh",
+            Target::Delta => "delta.gitconfig",
+            Target::Lazygit => "lazygit.yml",
+            Target::Dircolors => "dircolors",
+            Target::Tmux => "tmux.conf",
+            Target::Waybar => "waybar.css",
+            Target::Sway => "sway.conf",
+            Target::Rofi => "rasi",
+            Target::Dunst => "dunstrc",
+            Target::Tools | Target::Desktop => {
+                unreachable!("bundles are expanded before compiling")
+            }
+        };
+        format!("{name}.{extension}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Background {
+    Light,
+    Dark,
+}
+
+impl From<Background> for Kind {
+    fn from(value: Background) -> Self {
+        match value {
+            Background::Light => Kind::Light,
+            Background::Dark => Kind::Dark,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Gamut {
+    /// Only sRGB colors.
+    Srgb,
+    /// Colors mapped into the Display-P3 gamut.
+    DisplayP3,
+    /// Colors as computed from the configuration, leaving gamut mapping to the browser.
+    Unbounded,
+}
+
+impl From<Gamut> for CssGamut {
+    fn from(value: Gamut) -> Self {
+        match value {
+            Gamut::Srgb => CssGamut::Srgb,
+            Gamut::DisplayP3 => CssGamut::DisplayP3,
+            Gamut::Unbounded => CssGamut::Unbounded,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Cvd {
+    Protan,
+    Deutan,
+    Tritan,
+}
+
+impl From<Cvd> for cvd::Deficiency {
+    fn from(value: Cvd) -> Self {
+        match value {
+            Cvd::Protan => cvd::Deficiency::Protan,
+            Cvd::Deutan => cvd::Deficiency::Deutan,
+            Cvd::Tritan => cvd::Deficiency::Tritan,
+        }
+    }
 }
 
 /// Compile a (Neo)vim color scheme from a hi.nvim.rs theme configuration.
 ///
 /// The color scheme is written to standard output.
 #[derive(Parser, Debug)]
-#[command(version)]
+#[command(version, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    compile: CompileArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check a color scheme for readability problems. Problems are written to standard output.
+    /// The exit status is non-zero if problems were found.
+    Check(CheckArgs),
+    /// Import an existing color scheme as a hi.nvim.rs configuration. The configuration is
+    /// written to standard output, and a report of the colors that differ from the original to
+    /// standard error.
+    Import(ImportArgs),
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ImportFormat {
+    /// A Vim or Neovim color scheme: Vim script `highlight` commands, Lua `nvim_set_hl` calls,
+    /// or the output of `:highlight`.
+    Vim,
+    /// A base16 or base24 palette, in YAML.
+    Base16,
+    /// A VS Code color theme. Included themes are read relative to the theme.
+    Vscode,
+    /// A TextMate (`.tmTheme`) color scheme.
+    Tmtheme,
+}
+
+#[derive(Args, Debug)]
+struct CompileArgs {
     /// The compilation target. When Vim is chosen as target, highlight groups with unsupported
     /// names are removed. Supported characters in Vim correspond to the regexp [a-zA-Z0-9_].
     /// Neovim adds two characters, supporting regexp [a-zA-Z0-9_\.@]*.
     #[arg(short, long, value_name = "target", value_enum, default_value_t = Target::Neovim)]
     target: Target,
 
+    /// Compile a `notermguicolors` color scheme using the terminal's 16 ANSI colors. Theme
+    /// elements are mapped onto the closest color of the palette given in the configuration's
+    /// `[terminal16]` section, or in `--terminal-palette`.
+    #[arg(long)]
+    terminal16: bool,
+
+    /// A terminal palette file with the terminal's 16 ANSI colors. Colors are given in hexadecimal
+    /// notation, either one per line in slot order, or named by slot (e.g. `color4 #81a2be`, as in
+    /// Xresources and kitty configurations). Implies `--terminal16`.
+    #[arg(long, value_name = "file")]
+    terminal_palette: Option<PathBuf>,
+
+    /// Compile only the light or dark theme, named `<name>-light` or `<name>-dark`. Give this
+    /// twice to compile two separately named color schemes, which requires `--output-dir`.
+    /// Terminal emulator targets always compile the themes separately, by default both.
+    #[arg(short, long, value_name = "background", value_enum)]
+    background: Vec<Background>,
+
+    /// When compiling only the light or dark theme, also set (Neo)vim's `background` option when
+    /// the color scheme is loaded.
+    #[arg(long)]
+    force_background: bool,
+
+    /// The gamut of the colors of the CSS target. Unless `srgb`, the colors are also written as
+    /// `oklch()` colors in this gamut, overriding the sRGB colors in browsers that support them.
+    /// Defaults to `unbounded`.
+    #[arg(long, value_name = "gamut", value_enum)]
+    gamut: Option<Gamut>,
+
+    /// Write the color schemes to files named after the color schemes in this directory (e.g.,
+    /// `highlow.vim` or `highlow-dark.conf`), instead of to standard output. The directory is
+    /// created if it does not exist.
+    #[arg(short, long, value_name = "dir")]
+    output_dir: Option<PathBuf>,
+
     /// Color scheme input file. Reads from standard input if not set.
     // TODO: perhaps accept multiple files, merging the configurations (allowing for base
     // configurations overridden by more specific configurations)
     file: Option<PathBuf>,
 }
 
-fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("checks").required(true).multiple(true).args(["cvd", "contrast", "distinguishable"])))]
+struct CheckArgs {
+    /// Check whether semantically paired theme elements (e.g., `diagnostics.error` and
+    /// `diagnostics.ok`) remain distinguishable with a color vision deficiency.
+    #[arg(long, value_name = "deficiency", value_enum)]
+    cvd: Option<Cvd>,
+
+    /// The theme namespaces whose elements are semantically paired. All elements within a
+    /// namespace should be distinguishable from each other.
+    #[arg(
+        long,
+        value_name = "namespace",
+        default_values = ["diagnostics", "vcs", "diff"],
+    )]
+    cvd_namespace: Vec<String>,
+
+    /// The DeltaEOK below which paired elements are reported as hard to distinguish.
+    #[arg(long, value_name = "delta", default_value_t = 0.05)]
+    cvd_threshold: f32,
+
+    /// Write a copy of the color scheme with the deficiency simulated to this file, for
+    /// previewing. The copy is compiled for `--target`.
+    #[arg(long, value_name = "file")]
+    cvd_simulate: Option<PathBuf>,
+
+    /// The compilation target of the simulated color scheme.
+    #[arg(short, long, value_name = "target", value_enum, default_value_t = Target::Neovim)]
+    target: Target,
+
+    /// Check the contrast between the foreground and background colors of every highlight group,
+    /// in both the light and dark theme.
+    #[arg(long)]
+    contrast: bool,
+
+    /// The minimal WCAG 2 contrast ratio of highlight groups.
+    #[arg(long, value_name = "ratio", default_value_t = contrast::Thresholds::default().wcag2)]
+    min_wcag2: f32,
+
+    /// The minimal absolute APCA lightness contrast (Lc) of highlight groups.
+    #[arg(long, value_name = "Lc", default_value_t = contrast::Thresholds::default().apca)]
+    min_apca: f32,
+
+    /// Check whether the colors of the theme elements within a namespace (by default `syn`) are
+    /// distinguishable from each other, in both the light and dark theme. Elements that share a
+    /// color definition are not reported.
+    #[arg(long)]
+    distinguishable: bool,
+
+    /// The theme namespaces whose elements should be distinguishable from each other.
+    #[arg(long, value_name = "namespace", default_values = ["syn"])]
+    distinguishable_namespace: Vec<String>,
+
+    /// The DeltaEOK below which theme elements are reported as indistinguishable, as a multiple
+    /// of the just-noticeable difference (a DeltaEOK of 0.02).
+    #[arg(long, value_name = "multiple", default_value_t = 2.)]
+    jnd_multiple: f32,
+
+    /// Write the results as JSON. This includes the contrast of highlight groups that pass the
+    /// contrast check.
+    #[arg(long)]
+    json: bool,
+
+    /// Color scheme input file. Reads from standard input if not set.
+    file: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct ImportArgs {
+    /// The format of the color scheme to import.
+    #[arg(value_enum)]
+    format: ImportFormat,
+
+    /// The name of the imported color scheme. Defaults to the name the color scheme declares, or
+    /// the name of the file.
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Color scheme file. Reads from standard input if not set.
+    file: Option<PathBuf>,
+}
 
-    let config = if let Some(file) = cli.file {
-        std::fs::read_to_string(file)?
+fn read_config(file: Option<PathBuf>) -> anyhow::Result<String> {
+    if let Some(file) = file {
+        Ok(std::fs::read_to_string(file)?)
     } else {
         let mut config = String::with_capacity(16_384 /* 16 KiB */);
         std::io::stdin().read_to_string(&mut config).unwrap();
-        config
+        Ok(config)
+    }
+}
+
+fn compile(
+    colorscheme: &Colorscheme,
+    target: Target,
+    options: &CompileOptions,
+    gamut: Gamut,
+) -> Result<String, Error> {
+    match target {
+        Target::Neovim => hi_nvim_rs::compile_neovim_with_options(colorscheme, options),
+        Target::NeovimLua => hi_nvim_rs::compile_neovim_lua_with_options(colorscheme, options),
+        Target::Vim => hi_nvim_rs::compile_vim_with_options(colorscheme, options),
+        Target::Helix => hi_nvim_rs::compile_helix(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Vscode => hi_nvim_rs::compile_vscode(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Zed => match options.background {
+            Some(background) => hi_nvim_rs::compile_zed(colorscheme, &[background]),
+            None => hi_nvim_rs::compile_zed(colorscheme, &[Kind::Light, Kind::Dark]),
+        },
+        Target::Pygments => match options.background {
+            Some(background) => hi_nvim_rs::compile_pygments(colorscheme, &[background]),
+            None => hi_nvim_rs::compile_pygments(colorscheme, &[Kind::Light, Kind::Dark]),
+        },
+        Target::Json => match options.background {
+            Some(background) => hi_nvim_rs::compile_json(colorscheme, &[background]),
+            None => hi_nvim_rs::compile_json(colorscheme, &[Kind::Light, Kind::Dark]),
+        },
+        Target::Tmtheme => hi_nvim_rs::compile_tmtheme(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Sublime => hi_nvim_rs::compile_sublime_color_scheme(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Emacs => hi_nvim_rs::compile_emacs(colorscheme, options.background),
+        Target::Css => hi_nvim_rs::compile_css(colorscheme, options.background, gamut.into()),
+        Target::Kakoune => hi_nvim_rs::compile_kakoune(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Micro => hi_nvim_rs::compile_micro(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Jetbrains => hi_nvim_rs::compile_jetbrains(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Base16 => hi_nvim_rs::compile_base16(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Base24 => hi_nvim_rs::compile_base24(
+            colorscheme,
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Kitty
+        | Target::Alacritty
+        | Target::Wezterm
+        | Target::Foot
+        | Target::Ghostty
+        | Target::WindowsTerminal
+        | Target::Xresources => hi_nvim_rs::compile_terminal(
+            colorscheme,
+            target.emulator().unwrap(),
+            options.background.unwrap_or(colorscheme.kind()),
+        ),
+        Target::Fzf | Target::Delta | Target::Lazygit | Target::Dircolors | Target::Tmux => {
+            hi_nvim_rs::compile_tool(
+                colorscheme,
+                target.tool().unwrap(),
+                options.background.unwrap_or(colorscheme.kind()),
+            )
+        }
+        Target::Waybar | Target::Sway | Target::Rofi | Target::Dunst => {
+            hi_nvim_rs::compile_desktop(
+                colorscheme,
+                target.component().unwrap(),
+                options.background.unwrap_or(colorscheme.kind()),
+            )
+        }
+        Target::Tools | Target::Desktop => unreachable!("bundles are expanded before compiling"),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        None => compile_command(cli.compile),
+        Some(Command::Check(args)) => check_command(args),
+        Some(Command::Import(args)) => import_command(args),
+    }
+}
+
+fn compile_command(args: CompileArgs) -> anyhow::Result<()> {
+    let config = read_config(args.file)?;
+    let colorscheme = hi_nvim_rs::parse(&config)?;
+    if let Some(group) = colorscheme.link_cycle() {
+        eprintln!("warning: highlight group {group} is part of a link cycle");
+    }
+
+    if (args.terminal16 || args.terminal_palette.is_some()) && !args.target.uses_terminal16() {
+        anyhow::bail!(
+            "`--terminal16` and `--terminal-palette` are only supported by the `neovim`, `neovim-lua` and `vim` targets"
+        );
+    }
+
+    if args.gamut.is_some() && !matches!(args.target, Target::Css) {
+        anyhow::bail!("`--gamut` is only supported by the `css` target");
+    }
+
+    let terminal16 = if let Some(file) = args.terminal_palette {
+        Some(terminal16::Palette::parse(&std::fs::read_to_string(file)?)?)
+    } else if args.terminal16 {
+        Some(*colorscheme.terminal16().ok_or_else(|| {
+            Error::InvalidTerminalPalette(
+                "no `[terminal16]` section in the configuration and no `--terminal-palette` given"
+                    .to_owned(),
+            )
+        })?)
+    } else {
+        None
+    };
+
+    let backgrounds: Vec<Option<Kind>> = if !args.background.is_empty() {
+        args.background.iter().map(|&b| Some(b.into())).collect()
+    } else if args.target.per_background() {
+        vec![Some(Kind::Light), Some(Kind::Dark)]
+    } else {
+        vec![None]
     };
+    let duplicate = (1..backgrounds.len()).find(|&i| backgrounds[..i].contains(&backgrounds[i]));
+    if let Some(Some(background)) = duplicate.map(|i| backgrounds[i]) {
+        anyhow::bail!("`--background {background}` is given more than once");
+    }
+    let targets = args.target.expand();
+    if targets.len() > 1 && args.output_dir.is_none() {
+        anyhow::bail!(
+            "compiling the `{}` target writes several files and requires `--output-dir`",
+            args.target.to_possible_value().unwrap().get_name()
+        );
+    }
+    if backgrounds.len() > 1 && args.output_dir.is_none() {
+        anyhow::bail!(
+            "compiling multiple color schemes requires `--output-dir` (or a single `--background`)"
+        );
+    }
+
+    if let Some(output_dir) = &args.output_dir {
+        std::fs::create_dir_all(output_dir)
+            .with_context(|| format!("failed to create `{}`", output_dir.display()))?;
+    }
+
+    if let Some(palette) = &terminal16 {
+        // a color scheme compiled without a background holds both themes
+        let kinds = backgrounds.iter().flat_map(|background| match background {
+            Some(background) => vec![*background],
+            None => vec![Kind::Light, Kind::Dark],
+        });
+        for background in kinds {
+            let theme = colorscheme.theme(background);
+            for collision in terminal16::collisions(theme, palette, "syn") {
+                let elements = collision
+                    .elements
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                eprintln!(
+                    "warning: {background} theme: distinct syntax elements {elements} collapse onto terminal color {slot} ({slot_name})",
+                    slot = collision.slot,
+                    slot_name = terminal16::slot_name(collision.slot),
+                );
+            }
+        }
+    }
+
+    for &background in &backgrounds {
+        let options = CompileOptions {
+            terminal16: terminal16.as_ref(),
+            background,
+            force_background: args.force_background,
+        };
 
-    let colorscheme = colorscheme::parse(&config)?;
+        for &target in &targets {
+            let program = compile(
+                &colorscheme,
+                target,
+                &options,
+                args.gamut.unwrap_or(Gamut::Unbounded),
+            )?;
 
-    let program = match cli.target {
-        Target::Neovim => compiler_neovim::compile(&colorscheme)?,
-        Target::Vim => compiler_vim::compile(&colorscheme)?,
+            if let Some(output_dir) = &args.output_dir {
+                let path = output_dir.join(target.file_name(&options.colors_name(&colorscheme)));
+                std::fs::write(&path, program + "\n")
+                    .with_context(|| format!("failed to write `{}`", path.display()))?;
+            } else {
+                println!("{}", &program);
+            }
+        }
+    }
+
+    if let (Target::Vscode, Some(output_dir)) = (args.target, &args.output_dir) {
+        let backgrounds: Vec<Kind> = backgrounds.into_iter().flatten().collect();
+        let package = hi_nvim_rs::compile_vscode_package(&colorscheme, &backgrounds)?;
+        let path = output_dir.join("package.json");
+        std::fs::write(&path, package + "\n")
+            .with_context(|| format!("failed to write `{}`", path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn check_command(args: CheckArgs) -> anyhow::Result<()> {
+    let config = read_config(args.file)?;
+    let colorscheme = hi_nvim_rs::parse(&config)?;
+    if let Some(group) = colorscheme.link_cycle() {
+        eprintln!("warning: highlight group {group} is part of a link cycle");
+    }
+
+    #[derive(Default, Serialize)]
+    struct Report<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cvd: Option<Vec<cvd::Confusion<'a>>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        contrast: Option<Vec<contrast::GroupContrast<'a>>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        distinguishable: Option<Vec<distinguishability::SimilarPair<'a>>>,
+    }
+
+    let mut problems = 0;
+    let mut report = Report::default();
+
+    if let Some(deficiency) = args.cvd.map(cvd::Deficiency::from) {
+        let namespaces: Vec<&str> = args.cvd_namespace.iter().map(String::as_str).collect();
+        let confusions = cvd::check(&colorscheme, deficiency, &namespaces, args.cvd_threshold);
+        problems += confusions.len();
+
+        if !args.json {
+            for confusion in &confusions {
+                let [element1, element2] = confusion.elements;
+                println!(
+                    "{background} theme: {element1} and {element2} are hard to distinguish with {deficiency} (DeltaEOK {simulated:.3}, normally {normal:.3})",
+                    background = confusion.background,
+                    simulated = confusion.simulated_delta_eok,
+                    normal = confusion.delta_eok,
+                );
+            }
+        }
+
+        if let Some(file) = args.cvd_simulate {
+            if args.target.expand().len() > 1 {
+                anyhow::bail!("`--cvd-simulate` compiles a single file, choose a single target");
+            }
+            let simulated = deficiency.simulate_colorscheme(&colorscheme);
+            let program = compile(
+                &simulated,
+                args.target,
+                &CompileOptions::default(),
+                Gamut::Srgb,
+            )?;
+            std::fs::write(file, program + "\n")?;
+        }
+
+        report.cvd = Some(confusions);
+    }
+
+    if args.contrast {
+        let thresholds = contrast::Thresholds {
+            wcag2: args.min_wcag2,
+            apca: args.min_apca,
+        };
+        let contrasts = contrast::audit(&colorscheme, thresholds);
+        problems += contrasts.iter().filter(|contrast| contrast.flagged).count();
+
+        if !args.json {
+            for contrast in contrasts.iter().filter(|contrast| contrast.flagged) {
+                println!(
+                    "{background} theme: {group} has low contrast: {fg} on {bg} (WCAG 2 {wcag2:.2}:1, APCA Lc {apca:.1})",
+                    background = contrast.background,
+                    group = contrast.group,
+                    fg = contrast.fg,
+                    bg = contrast.bg,
+                    wcag2 = contrast.wcag2,
+                    apca = contrast.apca,
+                );
+            }
+        }
+
+        report.contrast = Some(contrasts);
+    }
+
+    if args.distinguishable {
+        let mut pairs = Vec::new();
+        for namespace in &args.distinguishable_namespace {
+            pairs.extend(distinguishability::audit(
+                &colorscheme,
+                namespace,
+                args.jnd_multiple,
+            ));
+        }
+        problems += pairs.len();
+
+        if !args.json {
+            for pair in &pairs {
+                let [element1, element2] = pair.elements;
+                let [color1, color2] = pair.colors;
+                println!(
+                    "{background} theme: {element1} ({color1}) and {element2} ({color2}) are hard to tell apart (DeltaEOK {delta:.3})",
+                    background = pair.background,
+                    delta = pair.delta_eok,
+                );
+            }
+        }
+
+        report.distinguishable = Some(pairs);
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+
+    if problems > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn import_command(args: ImportArgs) -> anyhow::Result<()> {
+    let file_name = args
+        .file
+        .as_ref()
+        .and_then(|file| file.file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned());
+    let directory = args
+        .file
+        .as_ref()
+        .and_then(|file| file.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let source = read_config(args.file)?;
+
+    let name = |declared: Option<String>| {
+        args.name
+            .clone()
+            .or(declared)
+            .or_else(|| file_name.clone())
+            .unwrap_or_else(|| "imported".to_owned())
     };
+    let (conversion, imported) = match args.format {
+        ImportFormat::Vim => {
+            let imported = import::parse_vim(&source);
+            let conversion = import::convert(&imported, &name(imported.name.clone()))?;
+            let groups = imported.highlights.len();
+            (conversion, format!("{groups} highlight groups"))
+        }
+        ImportFormat::Vscode | ImportFormat::Tmtheme => {
+            let imported = if let ImportFormat::Vscode = args.format {
+                import::parse_vscode(&source, &|path| {
+                    std::fs::read_to_string(directory.join(path))
+                        .map_err(|err| Error::ImportFailed(format!("{}: {err}", path.display())))
+                })?
+            } else {
+                import::parse_tmtheme(&source)?
+            };
+            let conversion = import::convert(&imported, &name(imported.name.clone()))?;
+            let groups = imported.highlights.len();
+            (conversion, format!("{groups} highlight groups"))
+        }
+        ImportFormat::Base16 => {
+            let palette = import::parse_base16(&source)?;
+            let conversion = import::convert_base16(&palette, &name(palette.name.clone()))?;
+            let system = if palette.colors.len() > 16 {
+                "base24"
+            } else {
+                "base16"
+            };
+            (conversion, format!("a {system} palette"))
+        }
+    };
+
+    for warning in &conversion.warnings {
+        eprintln!("warning: {warning}");
+    }
+    eprintln!(
+        "imported {imported}, merging {original} colors into {colors}",
+        original = conversion.original_colors,
+        colors = conversion.colors,
+    );
+    if conversion.differences.is_empty() {
+        eprintln!("all colors are reproduced exactly");
+    } else {
+        eprintln!(
+            "{} colors differ from the original:",
+            conversion.differences.len()
+        );
+        for difference in &conversion.differences {
+            eprintln!(
+                "  {group} {attribute}: {original} became {converted} (DeltaEOK {delta:.3})",
+                group = difference.group,
+                attribute = difference.attribute,
+                original = difference.original,
+                converted = difference.converted,
+                delta = difference.delta_eok,
+            );
+        }
+    }
 
-    println!("{}", &program);
+    println!("{}", conversion.configuration.trim_end());
 
     Ok(())
 }
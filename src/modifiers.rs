@@ -4,7 +4,7 @@ use serde::Deserialize;
 use crate::configuration::NamespacedColor;
 use crate::error::Error;
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
 pub struct ColorModifiers<'a> {
     pub saturate: Option<f32>,
     pub gamma: Option<f32>,
@@ -15,19 +15,6 @@ pub struct ColorModifiers<'a> {
     pub mix: Option<(NamespacedColor<'a>, f32)>,
 }
 
-impl Default for ColorModifiers<'_> {
-    fn default() -> ColorModifiers<'static> {
-        ColorModifiers {
-            saturate: None,
-            gamma: None,
-            lighten: None,
-            lighten_multiplier: None,
-            lighten_absolute: None,
-            mix: None,
-        }
-    }
-}
-
 impl ColorModifiers<'_> {
     /// Apply the modifier to the color.
     ///
@@ -0,0 +1,34 @@
+//! Options for compiling color schemes to (Neo)vim.
+
+use crate::colorscheme::Colorscheme;
+use crate::configuration::Kind;
+use crate::terminal16::Palette;
+
+/// Options for compiling color schemes to (Neo)vim.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompileOptions<'o> {
+    /// If set, compile a `notermguicolors` color scheme. Instead of exact colors, every theme
+    /// element refers to the closest ANSI color of this terminal palette.
+    pub terminal16: Option<&'o Palette>,
+    /// If set, compile only the light or dark theme, regardless of (Neo)vim's `background`
+    /// option. The color scheme is then named `<name>-light` or `<name>-dark`.
+    pub background: Option<Kind>,
+    /// When compiling only the light or dark theme, also set (Neo)vim's `background` option
+    /// accordingly.
+    pub force_background: bool,
+}
+
+impl CompileOptions<'_> {
+    /// The name the color scheme is compiled under (i.e., the value of `g:colors_name`).
+    pub fn colors_name(&self, colorscheme: &Colorscheme) -> String {
+        match self.background {
+            Some(background) => format!("{}-{background}", colorscheme.name()),
+            None => colorscheme.name().to_owned(),
+        }
+    }
+
+    /// The background to set (Neo)vim's `background` option to, if any.
+    pub(crate) fn forced_background(&self) -> Option<Kind> {
+        self.background.filter(|_| self.force_background)
+    }
+}
@@ -0,0 +1,154 @@
+//! The colors of terminal emulators.
+//!
+//! A color scheme declares terminal colors as elements of the `terminal` theme namespace: the 16
+//! ANSI colors named by slot (see [`SLOT_NAMES`]), plus the special colors named in
+//! [`SPECIAL_NAMES`]. Alternatively, the configuration's `[terminal]` section refers terminal
+//! colors to existing theme elements (e.g., `red = "diagnostics.error"`). Colors that are declared
+//! in neither fall back to the `ui` theme elements, and ANSI colors to the `vcs`, `syn` and
+//! `diagnostics` elements of matching hue.
+
+use crate::colorscheme::{Colorscheme, Rgb8};
+use crate::configuration::{Kind, NamespacedThemeElement};
+use crate::error::Error;
+use crate::terminal16::SLOT_NAMES;
+
+/// The theme namespace terminal colors are declared in.
+pub const THEME_NAMESPACE: &str = "terminal";
+
+/// The names of the terminal's special colors.
+pub const SPECIAL_NAMES: [&str; 4] = ["foreground", "background", "cursor", "selection"];
+
+/// Whether `name` names a terminal color (an ANSI color slot or a special color).
+pub fn is_terminal_color(name: &str) -> bool {
+    SLOT_NAMES.contains(&name) || SPECIAL_NAMES.contains(&name)
+}
+
+/// The theme element a terminal color falls back to in the light or dark theme. Black and white
+/// are the background and foreground, swapped for the light theme.
+fn fallback(name: &str, background: Kind) -> Option<NamespacedThemeElement<'static>> {
+    let light = background == Kind::Light;
+    let element = match name {
+        "foreground" | "cursor" => "ui.fg",
+        "background" => "ui.bg",
+        "selection" => "ui.bg_visual",
+        "black" if light => "ui.fg",
+        "black" => "ui.bg",
+        "white" if light => "ui.bg_dim",
+        "white" => "ui.fg",
+        "bright_black" => "ui.fg_very_dim",
+        "bright_white" if light => "ui.bg",
+        "bright_white" => "ui.pmenu_fg",
+        "red" => "vcs.removed",
+        "bright_red" => "diagnostics.error",
+        "green" => "vcs.added",
+        "bright_green" => "diagnostics.ok",
+        "yellow" => "vcs.changed",
+        "bright_yellow" => "diagnostics.warning",
+        "blue" => "syn.function",
+        "bright_blue" => "diagnostics.info",
+        "magenta" => "syn.keyword",
+        "bright_magenta" => "syn.statement",
+        "cyan" => "diagnostics.hint",
+        "bright_cyan" => "syn.constant",
+        _ => return None,
+    };
+
+    let (theme_namespace, element_name) = element.split_once('.')?;
+    Some(NamespacedThemeElement {
+        theme_namespace,
+        element_name,
+    })
+}
+
+/// The theme element a terminal color is declared as, if any. Fallbacks of special colors are
+/// not considered.
+pub fn theme_element<'a>(
+    colorscheme: &Colorscheme<'a>,
+    name: &'a str,
+) -> Option<NamespacedThemeElement<'a>> {
+    if let Some(&element) = colorscheme.terminal.get(name) {
+        return Some(element);
+    }
+
+    let element = NamespacedThemeElement {
+        theme_namespace: THEME_NAMESPACE,
+        element_name: name,
+    };
+    colorscheme.light_theme.get_color(element).map(|_| element)
+}
+
+/// The theme elements the 16 ANSI colors are declared as, in slot order. Returns `None` if the
+/// color scheme does not declare ANSI colors, and an error if it declares only some of them.
+pub fn ansi_elements<'a>(
+    colorscheme: &Colorscheme<'a>,
+) -> Result<Option<[NamespacedThemeElement<'a>; 16]>, Error> {
+    let elements = SLOT_NAMES.map(|name| theme_element(colorscheme, name));
+    if elements.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+
+    let mut ansi = [NamespacedThemeElement {
+        theme_namespace: "",
+        element_name: "",
+    }; 16];
+    for ((element, declared), name) in ansi.iter_mut().zip(elements).zip(SLOT_NAMES) {
+        *element = declared.ok_or_else(|| Error::TerminalColorMissing(name.to_owned()))?;
+    }
+
+    Ok(Some(ansi))
+}
+
+/// The colors of a terminal emulator theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalColors {
+    /// The 16 ANSI colors, in slot order.
+    pub ansi: [Rgb8; 16],
+    pub foreground: Rgb8,
+    pub background: Rgb8,
+    pub cursor: Rgb8,
+    /// The background color of selected text.
+    pub selection: Rgb8,
+}
+
+impl TerminalColors {
+    /// Get the terminal colors of the light or dark theme. If the color scheme declares no ANSI
+    /// colors, these fall back to theme elements of matching hue.
+    pub fn new(colorscheme: &Colorscheme, background: Kind) -> Result<TerminalColors, Error> {
+        let theme = colorscheme.theme(background);
+        let color = |name: &'static str, element: Option<NamespacedThemeElement>| {
+            element
+                .or_else(|| fallback(name, background))
+                .and_then(|element| theme.get_color(element))
+                .ok_or_else(|| Error::TerminalColorMissing(name.to_owned()))
+        };
+        let special = |name: &'static str| color(name, theme_element(colorscheme, name));
+
+        let elements = ansi_elements(colorscheme)?;
+        let mut ansi = [Rgb8 {
+            red: 0,
+            green: 0,
+            blue: 0,
+        }; 16];
+        for (slot, (ansi, name)) in ansi.iter_mut().zip(SLOT_NAMES).enumerate() {
+            *ansi = color(name, elements.map(|elements| elements[slot]))?;
+        }
+
+        Ok(TerminalColors {
+            ansi,
+            foreground: special("foreground")?,
+            background: special("background")?,
+            cursor: special("cursor")?,
+            selection: special("selection")?,
+        })
+    }
+
+    /// The 8 normal ANSI colors (slots 0 to 7).
+    pub fn normal(&self) -> &[Rgb8] {
+        &self.ansi[..8]
+    }
+
+    /// The 8 bright ANSI colors (slots 8 to 15).
+    pub fn bright(&self) -> &[Rgb8] {
+        &self.ansi[8..]
+    }
+}
@@ -0,0 +1,238 @@
+//! Mapping of theme colors onto a terminal's 16 ANSI colors.
+//!
+//! Instead of emitting exact colors (`termguicolors`), a color scheme can refer to the colors of
+//! the terminal's own palette (`notermguicolors`). Every theme element is assigned the ANSI color
+//! slot that is perceptually closest to it, as measured by DeltaEOK.
+
+use std::collections::BTreeMap;
+
+use crate::colorscheme::{Rgb8, Theme};
+use crate::configuration::NamespacedThemeElement;
+use crate::error::Error;
+
+/// The names of the 16 ANSI color slots, in slot order. These are the keys of a configuration's
+/// `[terminal16]` section.
+pub const SLOT_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// The name of an ANSI color slot (e.g., `bright_red` for slot 9).
+pub fn slot_name(slot: u8) -> &'static str {
+    SLOT_NAMES[usize::from(slot)]
+}
+
+/// A terminal's 16-color ANSI palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette(pub [Rgb8; 16]);
+
+impl Palette {
+    /// Build a palette from slot names (see [`SLOT_NAMES`]) mapped to hexadecimal colors, as
+    /// specified in a configuration's `[terminal16]` section. All 16 slots must be given.
+    pub fn from_slot_names(slots: &BTreeMap<&str, &str>) -> Result<Palette, Error> {
+        if let Some(unknown) = slots.keys().find(|name| !SLOT_NAMES.contains(name)) {
+            return Err(Error::InvalidTerminalPalette(format!(
+                "unknown color slot `{unknown}`"
+            )));
+        }
+
+        let mut colors = [Rgb8 {
+            red: 0,
+            green: 0,
+            blue: 0,
+        }; 16];
+        for (color, name) in colors.iter_mut().zip(SLOT_NAMES) {
+            let hex = slots.get(name).ok_or_else(|| {
+                Error::InvalidTerminalPalette(format!("color slot `{name}` is missing"))
+            })?;
+            *color = hex.parse()?;
+        }
+
+        Ok(Palette(colors))
+    }
+
+    /// Parse a terminal palette file.
+    ///
+    /// Every line containing a hexadecimal color (e.g., `#1d1f21`) defines a color. If lines name
+    /// their slot as `color<N>`, as in Xresources and kitty configurations (`*.color4: #81a2be`,
+    /// `color4 #81a2be`), only those lines are used. Otherwise the colors are assigned to the
+    /// slots in order, and the file must contain exactly 16 colors.
+    ///
+    /// Lines starting with `!`, `//` or `#` are comments, except for lines starting with a
+    /// hexadecimal color.
+    pub fn parse(palette_file: &str) -> Result<Palette, Error> {
+        let mut numbered = BTreeMap::new();
+        let mut ordered = Vec::new();
+
+        for line in palette_file.lines() {
+            let line = line.trim();
+            if line.starts_with('!') || line.starts_with("//") {
+                continue;
+            }
+            // kitty comments start with `#`, as do the bare colors of ordered palettes
+            if line.starts_with('#') {
+                let first = line.split_whitespace().next().unwrap_or_default();
+                if first.len() != 7 || first.parse::<Rgb8>().is_err() {
+                    continue;
+                }
+            }
+
+            let mut tokens = line
+                .split(|c: char| c.is_whitespace() || matches!(c, ':' | '=' | '"' | '\'' | ','))
+                .filter(|token| !token.is_empty());
+            let Some(color) = tokens
+                .clone()
+                .find(|token| token.starts_with('#') && token.len() == 7)
+                .and_then(|token| token.parse::<Rgb8>().ok())
+            else {
+                continue;
+            };
+            let slot = tokens.find_map(|token| {
+                let (_, index) = token.rsplit_once("color")?;
+                index.parse::<usize>().ok()
+            });
+
+            match slot {
+                Some(slot) if slot < 16 => {
+                    numbered.insert(slot, color);
+                }
+                // e.g., the 256-color cube, which is not part of the 16-color palette
+                Some(_) => {}
+                None => ordered.push(color),
+            }
+        }
+
+        let mut colors = [Rgb8 {
+            red: 0,
+            green: 0,
+            blue: 0,
+        }; 16];
+        if !numbered.is_empty() {
+            for (slot, color) in colors.iter_mut().enumerate() {
+                *color = *numbered.get(&slot).ok_or_else(|| {
+                    Error::InvalidTerminalPalette(format!("color{slot} is missing"))
+                })?;
+            }
+        } else if ordered.len() == 16 {
+            colors.copy_from_slice(&ordered);
+        } else {
+            return Err(Error::InvalidTerminalPalette(format!(
+                "expected 16 colors, found {}",
+                ordered.len()
+            )));
+        }
+
+        Ok(Palette(colors))
+    }
+
+    /// The slot of the palette color that is perceptually closest to `color`.
+    pub fn closest_slot(&self, color: Rgb8) -> u8 {
+        let mut closest = 0;
+        let mut closest_delta = f32::INFINITY;
+        for (slot, &palette_color) in (0..).zip(&self.0) {
+            let delta = color.delta_eok(palette_color);
+            if delta < closest_delta {
+                closest = slot;
+                closest_delta = delta;
+            }
+        }
+
+        closest
+    }
+}
+
+/// Theme elements with distinct colors that are mapped onto the same terminal color slot.
+#[derive(Debug, Clone)]
+pub struct Collision<'a> {
+    pub slot: u8,
+    pub elements: Vec<NamespacedThemeElement<'a>>,
+}
+
+/// Find the theme elements in `theme_namespace` (e.g., `syn`) whose colors are distinct in the
+/// theme, but which collapse onto the same slot of the terminal palette.
+pub fn collisions<'a>(
+    theme: &Theme<'a>,
+    palette: &Palette,
+    theme_namespace: &str,
+) -> Vec<Collision<'a>> {
+    let mut slots: BTreeMap<u8, Vec<(NamespacedThemeElement, Rgb8)>> = BTreeMap::new();
+    for (element, color) in theme.elements() {
+        if element.theme_namespace == theme_namespace {
+            slots
+                .entry(palette.closest_slot(color))
+                .or_default()
+                .push((element, color));
+        }
+    }
+
+    slots
+        .into_iter()
+        .filter(|(_, elements)| elements.iter().any(|&(_, color)| color != elements[0].1))
+        .map(|(slot, elements)| Collision {
+            slot,
+            elements: elements.into_iter().map(|(element, _)| element).collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Palette;
+
+    #[test]
+    fn parse_numbered_palette() {
+        let mut palette_file = String::from("! comment\n*.foreground: #c5c8c6\n");
+        for slot in (0..16).rev() {
+            palette_file += &format!("*.color{slot}: #0000{slot:02x}\n");
+        }
+        // Colors beyond the 16-color palette are ignored.
+        palette_file += "color16 #ffffff\n";
+
+        let palette = Palette::parse(&palette_file).unwrap();
+        for (slot, color) in (0..).zip(palette.0) {
+            assert_eq!(color.blue, slot);
+        }
+    }
+
+    #[test]
+    fn parse_commented_kitty_palette() {
+        let mut palette_file = String::new();
+        for slot in 0..16 {
+            palette_file += &format!("color{slot} #0000{slot:02x}\n");
+        }
+        // Later lines take precedence, but commented lines are ignored.
+        palette_file += "# color4 #ff0000\n#color5 #ff0000\n";
+
+        let palette = Palette::parse(&palette_file).unwrap();
+        assert_eq!(palette.0[4].blue, 4);
+        assert_eq!(palette.0[5].blue, 5);
+    }
+
+    #[test]
+    fn parse_ordered_palette() {
+        let palette_file = (0..16)
+            .map(|slot| format!("#{slot:02x}0000"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let palette = Palette::parse(&palette_file).unwrap();
+        assert_eq!(palette.0[15].red, 15);
+        assert_eq!(palette.closest_slot(palette.0[9]), 9);
+
+        assert!(Palette::parse("#000000\n#ffffff").is_err());
+    }
+}